@@ -0,0 +1,263 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Path, Query, State},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use tokio::fs;
+use utoipa::IntoParams;
+
+use crate::error::AppResult;
+use crate::loader::{model_entry, ModelFiles};
+use crate::registry::{ModelDetails, ModelFormat};
+use crate::{env_parse, model_details, AppState};
+
+/// Settings of the model management endpoints
+///
+/// They are disabled unless `ADMIN_TOKEN` is set, and take it as `Authorization: Bearer <token>`.
+pub struct Admin {
+    token: Option<String>,
+    max_upload_size: usize,
+}
+
+impl Admin {
+    pub fn from_env() -> anyhow::Result<Self> {
+        Ok(Self {
+            token: env_parse("ADMIN_TOKEN")?,
+            max_upload_size: env_parse("ADMIN_MAX_UPLOAD_SIZE")?.unwrap_or(1 << 30),
+        })
+    }
+
+    /// Check the bearer token, returning the status to send when it is missing or wrong
+    fn authorize(&self, headers: &HeaderMap) -> Result<(), (StatusCode, &'static str)> {
+        let Some(token) = &self.token else {
+            return Err((StatusCode::FORBIDDEN, "ADMIN_TOKEN is not set"));
+        };
+        let given = headers
+            .get(AUTHORIZATION)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.strip_prefix("Bearer "))
+            .unwrap_or_default();
+        // Comparing digests keeps the time taken independent of where the tokens differ
+        if Sha256::digest(given) != Sha256::digest(token) {
+            return Err((StatusCode::UNAUTHORIZED, "invalid admin token"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LoadModelRequest {
+    /// Name to load the model as, defaults to the model name of `path`
+    ident: Option<String>,
+    /// Model file on the server, `{ident}.sbv2`, `{ident}.aivmx` or `model_{ident}.onnx`
+    path: Option<String>,
+    /// Format of the uploaded body, `sbv2` or `aivmx`
+    format: Option<ModelFormat>,
+}
+
+fn bad_request(message: impl Into<String>) -> AppResult<Response> {
+    Ok((StatusCode::BAD_REQUEST, message.into()).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/models",
+    params(LoadModelRequest),
+    request_body(content = Vec<u8>, description = "Uploaded .sbv2 or .aivmx file, when `path` is not given", content_type = "application/octet-stream"),
+    responses(
+        (status = 201, description = "Return the loaded model", body = ModelDetails),
+        (status = 400, description = "Neither a readable `path` nor an upload with `ident` and `format`"),
+        (status = 401, description = "Wrong admin token"),
+        (status = 403, description = "`ADMIN_TOKEN` is not set"),
+        (status = 409, description = "A model is already loaded under `ident`"),
+        (status = 413, description = "Upload larger than `ADMIN_MAX_UPLOAD_SIZE`")
+    )
+)]
+pub async fn load_model(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(request): Query<LoadModelRequest>,
+    body: Body,
+) -> AppResult<Response> {
+    if let Err(rejection) = state.admin.authorize(&headers) {
+        return Ok(rejection.into_response());
+    }
+    let (ident, files, path) = match (request.path, request.format) {
+        (Some(path), _) => {
+            let path = PathBuf::from(path);
+            let files = match ModelFiles::read(&path).await {
+                Ok(files) => files,
+                Err(e) => return bad_request(format!("Error reading {}: {e}", path.display())),
+            };
+            let path = fs::canonicalize(&path).await.unwrap_or(path);
+            let ident = match request.ident {
+                Some(ident) => ident,
+                None => path
+                    .file_name()
+                    .and_then(|x| model_entry(&x.to_string_lossy()))
+                    .map(|(ident, _)| ident)
+                    .unwrap_or_default(),
+            };
+            (ident, files, Some(path))
+        }
+        (None, Some(format)) => {
+            let Some(ident) = request.ident else {
+                return bad_request("`ident` is required for uploads");
+            };
+            // The token is checked before the body is read
+            let bytes = match to_bytes(body, state.admin.max_upload_size).await {
+                Ok(bytes) => bytes.to_vec(),
+                Err(e) => return Ok((StatusCode::PAYLOAD_TOO_LARGE, e.to_string()).into_response()),
+            };
+            let files = match format {
+                ModelFormat::Sbv2 => ModelFiles::Sbv2(bytes),
+                ModelFormat::Aivmx => ModelFiles::Aivmx(bytes),
+                ModelFormat::OnnxJson => {
+                    return bad_request("onnx+json models are loaded by `path`");
+                }
+            };
+            (ident, files, None)
+        }
+        (None, None) => return bad_request("give `path`, or upload a model with `format`"),
+    };
+    let mut tts_model = state.tts_model.lock().await;
+    if tts_model.models().contains(&ident) {
+        return Ok((
+            StatusCode::CONFLICT,
+            format!("{ident} is already loaded, reload it instead"),
+        )
+            .into_response());
+    }
+    files.load(&mut tts_model, &state.registry, &ident, path)?;
+    log::info!("Loaded: {ident}");
+    if let Err(e) = state.warmup_model(&mut tts_model, &ident) {
+        log::error!("Error warming up {ident}: {e}");
+    }
+    Ok((StatusCode::CREATED, Json(model_details(&state, ident)?)).into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/models/{ident}",
+    params(("ident" = String, Path, description = "Model name")),
+    responses(
+        (status = 204, description = "The model is unloaded"),
+        (status = 401, description = "Wrong admin token"),
+        (status = 403, description = "`ADMIN_TOKEN` is not set"),
+        (status = 404, description = "No model is loaded under `ident`")
+    )
+)]
+pub async fn unload_model(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(ident): Path<String>,
+) -> AppResult<Response> {
+    if let Err(rejection) = state.admin.authorize(&headers) {
+        return Ok(rejection.into_response());
+    }
+    let mut tts_model = state.tts_model.lock().await;
+    if !tts_model.unload(&ident) {
+        return Err(sbv2_core::error::Error::ModelNotFoundError(ident).into());
+    }
+    state.registry.remove(&ident);
+    log::info!("Unloaded: {ident}");
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/models/{ident}/reload",
+    params(("ident" = String, Path, description = "Model name")),
+    responses(
+        (status = 200, description = "Return the reloaded model", body = ModelDetails),
+        (status = 401, description = "Wrong admin token"),
+        (status = 403, description = "`ADMIN_TOKEN` is not set"),
+        (status = 404, description = "No model is loaded under `ident`"),
+        (status = 409, description = "The model was uploaded and has no file to reload from")
+    )
+)]
+pub async fn reload_model(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(ident): Path<String>,
+) -> AppResult<Response> {
+    if let Err(rejection) = state.admin.authorize(&headers) {
+        return Ok(rejection.into_response());
+    }
+    let Some(path) = model_details(&state, ident.clone())?.path else {
+        return Ok((
+            StatusCode::CONFLICT,
+            format!("{ident} was uploaded, upload it again instead"),
+        )
+            .into_response());
+    };
+    // Read the files before taking the lock, so synthesis only waits for the session
+    let files = ModelFiles::read(&path).await?;
+    files.replace(
+        &mut *state.tts_model.lock().await,
+        &state.registry,
+        &ident,
+        Some(path),
+    )?;
+    log::info!("Reloaded: {ident}");
+    Ok(Json(model_details(&state, ident)?).into_response())
+}
+
+async fn set_pinned(
+    state: AppState,
+    headers: HeaderMap,
+    ident: String,
+    pinned: bool,
+) -> AppResult<Response> {
+    if let Err(rejection) = state.admin.authorize(&headers) {
+        return Ok(rejection.into_response());
+    }
+    let mut tts_model = state.tts_model.lock().await;
+    tts_model.set_pinned(&ident, pinned)?;
+    state.registry.refresh(&tts_model);
+    Ok(Json(model_details(&state, ident)?).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/models/{ident}/pin",
+    params(("ident" = String, Path, description = "Model name")),
+    responses(
+        (status = 200, description = "The model is never evicted by `HOLDER_MAX_LOADED_MODElS`", body = ModelDetails),
+        (status = 401, description = "Wrong admin token"),
+        (status = 403, description = "`ADMIN_TOKEN` is not set"),
+        (status = 404, description = "No model is loaded under `ident`")
+    )
+)]
+pub async fn pin_model(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(ident): Path<String>,
+) -> AppResult<Response> {
+    set_pinned(state, headers, ident, true).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/models/{ident}/pin",
+    params(("ident" = String, Path, description = "Model name")),
+    responses(
+        (status = 200, description = "The model can be evicted again", body = ModelDetails),
+        (status = 401, description = "Wrong admin token"),
+        (status = 403, description = "`ADMIN_TOKEN` is not set"),
+        (status = 404, description = "No model is loaded under `ident`")
+    )
+)]
+pub async fn unpin_model(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(ident): Path<String>,
+) -> AppResult<Response> {
+    set_pinned(state, headers, ident, false).await
+}
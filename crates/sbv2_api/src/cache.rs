@@ -0,0 +1,226 @@
+use axum::{
+    body::Bytes,
+    http::{
+        header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::fs;
+use tokio::sync::OnceCell;
+
+use crate::env_parse;
+
+#[derive(Default)]
+struct Memory {
+    entries: HashMap<String, Bytes>,
+    order: VecDeque<String>,
+}
+
+/// Synthesized audio keyed by a hash of the request, of the model files and of the server settings
+///
+/// Entries live in a bounded in-memory tier (`AUDIO_CACHE_SIZE` entries) and,
+/// when `AUDIO_CACHE_DIR` is set, in an unbounded on-disk tier that survives restarts.
+pub struct AudioCache {
+    capacity: usize,
+    dir: Option<PathBuf>,
+    max_age: u64,
+    /// Hash of the settings that change the audio of every model, see `AppState::new`
+    settings: String,
+    memory: Mutex<Memory>,
+    /// Syntheses in progress, shared by identical requests
+    inflight: Mutex<HashMap<String, Arc<OnceCell<Bytes>>>>,
+    /// Makes the temporary file of every write unique
+    writes: AtomicU64,
+}
+
+/// Removes an in-flight synthesis once a request stops waiting for it
+struct Inflight<'a> {
+    cache: &'a AudioCache,
+    key: &'a str,
+    cell: Arc<OnceCell<Bytes>>,
+}
+
+impl Drop for Inflight<'_> {
+    fn drop(&mut self) {
+        let mut inflight = self.cache.inflight.lock().unwrap();
+        if inflight
+            .get(self.key)
+            .is_some_and(|cell| Arc::ptr_eq(cell, &self.cell))
+        {
+            inflight.remove(self.key);
+        }
+    }
+}
+
+impl AudioCache {
+    pub async fn from_env(settings: String) -> anyhow::Result<Self> {
+        let dir: Option<PathBuf> = env_parse("AUDIO_CACHE_DIR")?;
+        if let Some(dir) = &dir {
+            fs::create_dir_all(dir).await?;
+        }
+        Ok(Self {
+            capacity: env_parse("AUDIO_CACHE_SIZE")?.unwrap_or(0),
+            dir,
+            max_age: env_parse("AUDIO_CACHE_MAX_AGE")?.unwrap_or(86400),
+            settings,
+            memory: Mutex::new(Memory::default()),
+            inflight: Mutex::new(HashMap::new()),
+            writes: AtomicU64::new(0),
+        })
+    }
+
+    /// Hash the endpoint name, the settings, the digest of the model files and every field of the request
+    ///
+    /// Reloading a model with other files or restarting with other settings changes the key,
+    /// so neither tier serves audio made before.
+    pub fn key<T: Serialize>(
+        &self,
+        endpoint: &str,
+        digest: &str,
+        request: &T,
+    ) -> anyhow::Result<String> {
+        let mut hasher = Sha256::new();
+        hasher.update(endpoint.as_bytes());
+        hasher.update([0]);
+        hasher.update(self.settings.as_bytes());
+        hasher.update([0]);
+        hasher.update(digest.as_bytes());
+        hasher.update([0]);
+        hasher.update(serde_json::to_vec(request)?);
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(format!("{key}.wav")))
+    }
+
+    fn get_memory(&self, key: &str) -> Option<Bytes> {
+        let mut memory = self.memory.lock().unwrap();
+        let data = memory.entries.get(key).cloned()?;
+        if let Some(i) = memory.order.iter().position(|x| x == key) {
+            if let Some(key) = memory.order.remove(i) {
+                memory.order.push_back(key);
+            }
+        }
+        Some(data)
+    }
+
+    fn insert_memory(&self, key: &str, data: Bytes) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut memory = self.memory.lock().unwrap();
+        if memory.entries.insert(key.to_string(), data).is_none() {
+            memory.order.push_back(key.to_string());
+        }
+        while memory.entries.len() > self.capacity {
+            match memory.order.pop_front() {
+                Some(key) => {
+                    memory.entries.remove(&key);
+                }
+                None => break,
+            }
+        }
+    }
+
+    pub async fn get(&self, key: &str) -> Option<Bytes> {
+        if let Some(data) = self.get_memory(key) {
+            return Some(data);
+        }
+        let data = Bytes::from(fs::read(self.path(key)?).await.ok()?);
+        self.insert_memory(key, data.clone());
+        Some(data)
+    }
+
+    pub async fn insert(&self, key: &str, data: Bytes) {
+        self.insert_memory(key, data.clone());
+        if let Some(path) = self.path(key) {
+            // Write to a temporary file of this write only, so readers never see a partial file
+            let write = self.writes.fetch_add(1, Ordering::Relaxed);
+            let tmp = path.with_extension(format!("{}.{write}.tmp", std::process::id()));
+            let result = match fs::write(&tmp, &data).await {
+                Ok(()) => fs::rename(&tmp, &path).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                log::warn!("Error writing audio cache {}: {e}", path.display());
+            }
+        }
+    }
+
+    /// Return the cached audio for `key`, or run `synthesize` and cache its output
+    ///
+    /// Requests whose `If-None-Match` matches the key get `304 Not Modified`. Identical requests
+    /// arriving while one is synthesized wait for its audio instead of synthesizing it again.
+    pub async fn respond<F>(
+        &self,
+        key: &str,
+        headers: &HeaderMap,
+        synthesize: F,
+    ) -> anyhow::Result<Response>
+    where
+        F: std::future::Future<Output = anyhow::Result<Vec<u8>>>,
+    {
+        let etag = format!("\"{key}\"");
+        let mut response_headers = HeaderMap::new();
+        response_headers.insert(ETAG, HeaderValue::from_str(&etag)?);
+        response_headers.insert(
+            CACHE_CONTROL,
+            HeaderValue::from_str(&format!("public, max-age={}", self.max_age))?,
+        );
+        if is_not_modified(headers, &etag) {
+            return Ok((StatusCode::NOT_MODIFIED, response_headers).into_response());
+        }
+        let data = match self.get(key).await {
+            Some(data) => {
+                response_headers.insert("x-cache", HeaderValue::from_static("HIT"));
+                data
+            }
+            None => {
+                let cell = self
+                    .inflight
+                    .lock()
+                    .unwrap()
+                    .entry(key.to_string())
+                    .or_default()
+                    .clone();
+                let inflight = Inflight {
+                    cache: self,
+                    key,
+                    cell,
+                };
+                // If the synthesis fails, the next waiting request runs its own
+                let data = inflight
+                    .cell
+                    .get_or_try_init(|| async {
+                        let data = Bytes::from(synthesize.await?);
+                        self.insert(key, data.clone()).await;
+                        anyhow::Ok(data)
+                    })
+                    .await?
+                    .clone();
+                response_headers.insert("x-cache", HeaderValue::from_static("MISS"));
+                data
+            }
+        };
+        response_headers.insert(CONTENT_TYPE, HeaderValue::from_static("audio/wav"));
+        Ok((response_headers, data).into_response())
+    }
+}
+
+fn is_not_modified(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|x| x.to_str().ok())
+        .flat_map(|x| x.split(','))
+        .map(|x| x.trim().trim_start_matches("W/"))
+        .any(|x| x == etag || x == "*")
+}
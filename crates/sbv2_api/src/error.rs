@@ -9,11 +9,23 @@ pub struct AppError(anyhow::Error);
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Something went wrong: {}", self.0),
-        )
-            .into_response()
+        use sbv2_core::error::Error;
+        // Input the text front end cannot read is the client's fault
+        let status = match self.0.downcast_ref::<Error>() {
+            Some(
+                Error::EmptyReadingError { .. }
+                | Error::UnknownMoraError { .. }
+                | Error::PhonemeMismatchError { .. }
+                | Error::UnknownPhoneError { .. }
+                | Error::PhoneLengthError { .. }
+                | Error::InvalidToneError { .. }
+                | Error::KanaParseError(_)
+                | Error::SpeakerError(_),
+            ) => StatusCode::UNPROCESSABLE_ENTITY,
+            Some(Error::ModelNotFoundError(_)) => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, format!("Something went wrong: {}", self.0)).into_response()
     }
 }
 
@@ -0,0 +1,174 @@
+use crate::registry::{LoadedFiles, ModelFormat, ModelRegistry};
+use sbv2_core::tts::TTSModelHolder;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio::fs;
+
+/// Ident and format of the model a file in `MODELS_PATH` belongs to
+pub fn model_entry(file_name: &str) -> Option<(String, ModelFormat)> {
+    if let Some(ident) = file_name
+        .strip_prefix("model_")
+        .and_then(|x| x.strip_suffix(".onnx"))
+    {
+        Some((ident.to_string(), ModelFormat::OnnxJson))
+    } else if let Some(ident) = file_name.strip_suffix(".sbv2") {
+        Some((ident.to_string(), ModelFormat::Sbv2))
+    } else {
+        file_name
+            .strip_suffix(".aivmx")
+            .map(|ident| (ident.to_string(), ModelFormat::Aivmx))
+    }
+}
+
+/// Files of a model, read without holding the `TTSModelHolder` lock
+pub enum ModelFiles {
+    Sbv2(Vec<u8>),
+    Aivmx(Vec<u8>),
+    OnnxJson {
+        style_vectors: Vec<u8>,
+        vits2: Vec<u8>,
+        /// `config_{ident}.json`, only needed for speaker and style names
+        config: Option<Vec<u8>>,
+    },
+}
+
+impl ModelFiles {
+    /// Read the model `path` belongs to, with `style_vectors_{ident}.json` next to `model_{ident}.onnx`
+    pub async fn read(path: &Path) -> anyhow::Result<Self> {
+        let file_name = path
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let (ident, format) = model_entry(&file_name)
+            .ok_or_else(|| anyhow::anyhow!("not a model file: {}", path.display()))?;
+        Ok(match format {
+            ModelFormat::Sbv2 => ModelFiles::Sbv2(fs::read(path).await?),
+            ModelFormat::Aivmx => ModelFiles::Aivmx(fs::read(path).await?),
+            ModelFormat::OnnxJson => {
+                let dir = path.parent().unwrap_or(Path::new("."));
+                ModelFiles::OnnxJson {
+                    style_vectors: fs::read(dir.join(format!("style_vectors_{ident}.json")))
+                        .await?,
+                    vits2: fs::read(path).await?,
+                    config: fs::read(dir.join(format!("config_{ident}.json")))
+                        .await
+                        .ok(),
+                }
+            }
+        })
+    }
+
+    pub fn format(&self) -> ModelFormat {
+        match self {
+            ModelFiles::Sbv2(_) => ModelFormat::Sbv2,
+            ModelFiles::Aivmx(_) => ModelFormat::Aivmx,
+            ModelFiles::OnnxJson { .. } => ModelFormat::OnnxJson,
+        }
+    }
+
+    /// Hash of every file of the model
+    pub fn digest(&self) -> String {
+        let mut hasher = Sha256::new();
+        match self {
+            ModelFiles::Sbv2(bytes) | ModelFiles::Aivmx(bytes) => hasher.update(bytes),
+            ModelFiles::OnnxJson {
+                style_vectors,
+                vits2,
+                config,
+            } => {
+                for file in [Some(style_vectors), Some(vits2), config.as_ref()] {
+                    // Length-prefixed, so moving bytes between files changes the hash
+                    let file = file.map(Vec::as_slice).unwrap_or_default();
+                    hasher.update((file.len() as u64).to_le_bytes());
+                    hasher.update(file);
+                }
+            }
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Load the model as `ident` and record it in `registry`, `path` is where it is reloaded from
+    pub fn load(
+        self,
+        tts_model: &mut TTSModelHolder,
+        registry: &ModelRegistry,
+        ident: &str,
+        path: Option<PathBuf>,
+    ) -> anyhow::Result<()> {
+        let start = Instant::now();
+        let format = self.format();
+        let digest = self.digest();
+        let file_size = match self {
+            ModelFiles::Sbv2(sbv2_bytes) => {
+                let file_size = sbv2_bytes.len();
+                tts_model.load_sbv2file(ident, sbv2_bytes)?;
+                file_size
+            }
+            ModelFiles::Aivmx(aivmx_bytes) => {
+                let file_size = aivmx_bytes.len();
+                tts_model.load_aivmx(ident, aivmx_bytes)?;
+                file_size
+            }
+            ModelFiles::OnnxJson {
+                style_vectors,
+                vits2,
+                config,
+            } => {
+                let mut file_size = style_vectors.len() + vits2.len();
+                tts_model.load(ident, style_vectors, vits2)?;
+                // Speaker names of multi-speaker models are optional
+                if let Some(config) = config {
+                    file_size += config.len();
+                    if let Err(e) = tts_model.load_config(ident, config) {
+                        log::warn!("Error loading config of {ident}: {e}");
+                    }
+                }
+                file_size
+            }
+        };
+        registry.insert(
+            tts_model,
+            ident,
+            LoadedFiles {
+                format,
+                file_size: file_size as u64,
+                digest,
+                path,
+            },
+            start.elapsed(),
+        )
+    }
+
+    /// Load the model as `ident` in place of the one loaded under it, keeping whether it is pinned and warmed up
+    ///
+    /// The old model keeps serving if the new one fails to load.
+    pub fn replace(
+        self,
+        tts_model: &mut TTSModelHolder,
+        registry: &ModelRegistry,
+        ident: &str,
+        path: Option<PathBuf>,
+    ) -> anyhow::Result<()> {
+        let info = tts_model.model_info(ident).ok();
+        let old = tts_model.take(ident);
+        if let Err(e) = self.load(tts_model, registry, ident, path) {
+            match old {
+                Some(old) => tts_model.restore(old),
+                None => {
+                    tts_model.unload(ident);
+                }
+            }
+            return Err(e);
+        }
+        if let Some(info) = info {
+            tts_model.set_pinned(ident, info.pinned)?;
+            if info.warmup {
+                tts_model.set_warmup(ident, true)?;
+                tts_model.warmup_model(ident)?;
+            }
+            registry.refresh(tts_model);
+        }
+        Ok(())
+    }
+}
@@ -1,24 +1,65 @@
 use axum::{
-    extract::State,
-    http::header::CONTENT_TYPE,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     routing::{get, post},
     Json, Router,
 };
+use sbv2_core::analysis::analyze_text;
+use sbv2_core::jtalk::JTalk;
+use sbv2_core::model::SessionConfig;
+use sbv2_core::tokenizer::{get_tokenizer, Tokenizer};
 use sbv2_core::tts::{SynthesizeOptions, TTSModelHolder};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 use std::env;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use tokio::fs;
 use tokio::sync::Mutex;
-use utoipa::{OpenApi, ToSchema};
+use utoipa::{IntoParams, OpenApi, ToSchema};
 use utoipa_scalar::{Scalar, Servable};
 
+mod admin;
+mod cache;
 mod error;
+mod loader;
+mod registry;
+#[cfg(test)]
+mod tests;
+mod watcher;
+use crate::admin::Admin;
+use crate::cache::AudioCache;
 use crate::error::AppResult;
+use crate::loader::{model_entry, ModelFiles};
+use crate::registry::{ModelDetails, ModelFormat, ModelRegistry, SpeakerInfo};
 
 #[derive(OpenApi)]
-#[openapi(paths(models, synthesize), components(schemas(SynthesizeRequest)))]
+#[openapi(
+    paths(
+        models,
+        model_info,
+        speakers,
+        admin::load_model,
+        admin::unload_model,
+        admin::reload_model,
+        admin::pin_model,
+        admin::unpin_model,
+        synthesize,
+        synthesize_get,
+        synthesize_phones,
+        analyze,
+        ready
+    ),
+    components(schemas(
+        SynthesizeRequest,
+        SynthesizePhonesRequest,
+        AnalyzeRequest,
+        ModelDetails,
+        ModelFormat,
+        SpeakerInfo
+    ))
+)]
 struct ApiDoc;
 
 #[utoipa::path(
@@ -32,6 +73,44 @@ async fn models(State(state): State<AppState>) -> AppResult<impl IntoResponse> {
     Ok(Json(state.tts_model.lock().await.models()))
 }
 
+fn model_details(state: &AppState, ident: String) -> AppResult<ModelDetails> {
+    Ok(state
+        .registry
+        .get(&ident)
+        .ok_or(sbv2_core::error::Error::ModelNotFoundError(ident))?)
+}
+
+#[utoipa::path(
+    get,
+    path = "/models/{ident}",
+    params(("ident" = String, Path, description = "Model name")),
+    responses(
+        (status = 200, description = "Return the format, styles, speakers and load state of the model", body = ModelDetails),
+        (status = 404, description = "No model is loaded under `ident`")
+    )
+)]
+async fn model_info(
+    State(state): State<AppState>,
+    Path(ident): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    Ok(Json(model_details(&state, ident)?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/models/{ident}/speakers",
+    params(("ident" = String, Path, description = "Model name")),
+    responses(
+        (status = 200, description = "Return the speakers of the model, empty when unknown", body = Vec<SpeakerInfo>),
+    )
+)]
+async fn speakers(
+    State(state): State<AppState>,
+    Path(ident): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    Ok(Json(model_details(&state, ident)?.speakers))
+}
+
 fn sdp_default() -> f32 {
     0.0
 }
@@ -48,7 +127,12 @@ fn speaker_id_default() -> i64 {
     0
 }
 
-#[derive(Deserialize, ToSchema)]
+fn assist_text_weight_default() -> f32 {
+    0.7
+}
+
+#[derive(Deserialize, Serialize, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct SynthesizeRequest {
     text: String,
     ident: String,
@@ -64,6 +148,22 @@ struct SynthesizeRequest {
     #[serde(default = "speaker_id_default")]
     #[schema(example = 0_i64)]
     speaker_id: i64,
+    /// Speaker name, see `/models/{ident}/speakers`, overrides `speaker_id`
+    speaker_name: Option<String>,
+    /// Treat `text` as AquesTalk-like kana notation, e.g. `コンニチワ'/キョ'ウワ`
+    #[serde(default)]
+    #[schema(example = false)]
+    is_kana: bool,
+    /// Text whose BERT feature is blended in to steer emotion
+    #[schema(example = "とても嬉しいです")]
+    assist_text: Option<String>,
+    #[serde(default = "assist_text_weight_default")]
+    #[schema(example = 0.7_f32)]
+    assist_text_weight: f32,
+    /// Run BERT for the next sentence while the current one is synthesized, for multi-line `text`
+    #[serde(default)]
+    #[schema(example = false)]
+    pipeline: bool,
 }
 
 #[utoipa::path(
@@ -71,137 +171,492 @@ struct SynthesizeRequest {
     path = "/synthesize",
     request_body = SynthesizeRequest,
     responses(
-        (status = 200, description = "Return audio/wav", body = Vec<u8>, content_type = "audio/wav")
+        (status = 200, description = "Return audio/wav", body = Vec<u8>, content_type = "audio/wav"),
+        (status = 304, description = "`If-None-Match` matches the ETag of the audio")
     )
 )]
 async fn synthesize(
     State(state): State<AppState>,
-    Json(SynthesizeRequest {
+    headers: HeaderMap,
+    Json(request): Json<SynthesizeRequest>,
+) -> AppResult<impl IntoResponse> {
+    synthesize_request(state, headers, request).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/synthesize",
+    params(SynthesizeRequest),
+    responses(
+        (status = 200, description = "Return audio/wav", body = Vec<u8>, content_type = "audio/wav"),
+        (status = 304, description = "`If-None-Match` matches the ETag of the audio")
+    )
+)]
+async fn synthesize_get(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(request): Query<SynthesizeRequest>,
+) -> AppResult<impl IntoResponse> {
+    synthesize_request(state, headers, request).await
+}
+
+async fn synthesize_request(
+    state: AppState,
+    headers: HeaderMap,
+    request: SynthesizeRequest,
+) -> AppResult<impl IntoResponse> {
+    let digest = model_details(&state, request.ident.clone())?.digest;
+    let key = state.cache.key("synthesize/wav", &digest, &request)?;
+    let SynthesizeRequest {
         text,
         ident,
         sdp_ratio,
         length_scale,
         style_id,
         speaker_id,
-    }): Json<SynthesizeRequest>,
-) -> AppResult<impl IntoResponse> {
-    log::debug!("processing request: text={text}, ident={ident}, sdp_ratio={sdp_ratio}, length_scale={length_scale}");
-    let buffer = {
-        let mut tts_model = state.tts_model.lock().await;
-        tts_model.easy_synthesize(
-            &ident,
-            &text,
-            style_id,
-            speaker_id,
-            SynthesizeOptions {
+        speaker_name,
+        is_kana,
+        assist_text,
+        assist_text_weight,
+        pipeline,
+    } = request;
+    log::debug!("processing request: text={text}, ident={ident}, sdp_ratio={sdp_ratio}, length_scale={length_scale}, is_kana={is_kana}");
+    Ok(state
+        .cache
+        .respond(&key, &headers, async {
+            let mut tts_model = state.tts_model.lock().await;
+            let speaker_id = match &speaker_name {
+                Some(name) => tts_model.speaker_id(&ident, name)?,
+                None => speaker_id,
+            };
+            let options = SynthesizeOptions {
                 sdp_ratio,
                 length_scale,
+                assist_text,
+                assist_text_weight,
+                pipeline,
                 ..Default::default()
-            },
-        )?
-    };
-    Ok(([(CONTENT_TYPE, "audio/wav")], buffer))
+            };
+            let audio = if is_kana {
+                tts_model.easy_synthesize_kana(&ident, &text, style_id, speaker_id, options)
+            } else {
+                tts_model.easy_synthesize(&ident, &text, style_id, speaker_id, options)
+            };
+            state.registry.refresh(&tts_model);
+            Ok(audio?)
+        })
+        .await?)
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+struct SynthesizePhonesRequest {
+    ident: String,
+    /// Phones named as in `JP_SYMBOLS`, or punctuations
+    #[schema(example = json!(["k", "o", "N", "n", "i", "ch", "i", "w", "a"]))]
+    phones: Vec<String>,
+    /// Tone of each phone, 0 or 1
+    #[schema(example = json!([0, 0, 1, 1, 1, 1, 1, 1, 1]))]
+    tones: Vec<i32>,
+    /// Companion text for the BERT features, zero-filled when omitted
+    #[schema(example = "こんにちは")]
+    text: Option<String>,
+    #[serde(default = "sdp_default")]
+    #[schema(example = 0.0_f32)]
+    sdp_ratio: f32,
+    #[serde(default = "length_default")]
+    #[schema(example = 1.0_f32)]
+    length_scale: f32,
+    #[serde(default = "style_id_default")]
+    #[schema(example = 0_i32)]
+    style_id: i32,
+    #[serde(default = "speaker_id_default")]
+    #[schema(example = 0_i64)]
+    speaker_id: i64,
+    /// Speaker name, see `/models/{ident}/speakers`, overrides `speaker_id`
+    speaker_name: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/synthesize_phones",
+    request_body = SynthesizePhonesRequest,
+    responses(
+        (status = 200, description = "Return audio/wav", body = Vec<u8>, content_type = "audio/wav")
+    )
+)]
+async fn synthesize_phones(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<SynthesizePhonesRequest>,
+) -> AppResult<impl IntoResponse> {
+    let digest = model_details(&state, request.ident.clone())?.digest;
+    let key = state
+        .cache
+        .key("synthesize_phones/wav", &digest, &request)?;
+    let SynthesizePhonesRequest {
+        ident,
+        phones,
+        tones,
+        text,
+        sdp_ratio,
+        length_scale,
+        style_id,
+        speaker_id,
+        speaker_name,
+    } = request;
+    log::debug!("processing request: phones={phones:?}, tones={tones:?}, ident={ident}");
+    Ok(state
+        .cache
+        .respond(&key, &headers, async {
+            let mut tts_model = state.tts_model.lock().await;
+            let speaker_id = match &speaker_name {
+                Some(name) => tts_model.speaker_id(&ident, name)?,
+                None => speaker_id,
+            };
+            let audio = tts_model.easy_synthesize_phones(
+                &ident,
+                phones,
+                tones,
+                text.as_deref(),
+                style_id,
+                speaker_id,
+                SynthesizeOptions {
+                    sdp_ratio,
+                    length_scale,
+                    ..Default::default()
+                },
+            );
+            state.registry.refresh(&tts_model);
+            Ok(audio?)
+        })
+        .await?)
+}
+
+#[derive(Deserialize, ToSchema)]
+struct AnalyzeRequest {
+    #[schema(example = "今日はいい天気ですね")]
+    text: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/analyze",
+    request_body = AnalyzeRequest,
+    responses(
+        (status = 200, description = "Return the result of every text front-end stage", content_type = "application/json")
+    )
+)]
+async fn analyze(
+    State(state): State<AppState>,
+    Json(AnalyzeRequest { text }): Json<AnalyzeRequest>,
+) -> AppResult<impl IntoResponse> {
+    let analysis = tokio::task::spawn_blocking(move || {
+        let front_end = &state.front_end;
+        analyze_text(&text, &front_end.jtalk, &front_end.tokenizer)
+    })
+    .await??;
+    Ok(Json(analysis))
+}
+
+/// Parse the variable `key`, none when it is unset or empty and an error when it is malformed
+fn env_parse<T>(key: &str) -> anyhow::Result<Option<T>>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    match env::var(key) {
+        Ok(value) if value.is_empty() => Ok(None),
+        Ok(value) => match value.parse() {
+            Ok(value) => Ok(Some(value)),
+            Err(e) => anyhow::bail!("invalid {key}={value:?}: {e}"),
+        },
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(e) => anyhow::bail!("invalid {key}: {e}"),
+    }
+}
+
+fn session_config_from_env() -> anyhow::Result<SessionConfig> {
+    let default = SessionConfig::default();
+    Ok(SessionConfig {
+        backend: env_parse("INFERENCE_BACKEND")?.unwrap_or(default.backend),
+        intra_threads: env_parse("ORT_INTRA_THREADS")?.unwrap_or(default.intra_threads),
+        inter_threads: env_parse("ORT_INTER_THREADS")?.unwrap_or(default.inter_threads),
+        optimization_level: env_parse("ORT_OPTIMIZATION_LEVEL")?
+            .unwrap_or(default.optimization_level),
+        parallel_execution: env_parse("ORT_PARALLEL_EXECUTION")?
+            .unwrap_or(default.parallel_execution),
+        memory_pattern: env_parse("ORT_MEMORY_PATTERN")?.unwrap_or(default.memory_pattern),
+        cpu_arena: env_parse("ORT_CPU_ARENA")?.unwrap_or(default.cpu_arena),
+        optimized_model_dir: env_parse("ORT_OPTIMIZED_MODEL_DIR")?,
+        model_cache_dir: env_parse("MODEL_CACHE_DIR")?,
+    })
 }
 
 #[derive(Clone)]
 struct AppState {
     tts_model: Arc<Mutex<TTSModelHolder>>,
+    front_end: Arc<FrontEnd>,
+    registry: Arc<ModelRegistry>,
+    admin: Arc<Admin>,
+    cache: Arc<AudioCache>,
+    warmup_models: Arc<WarmupModels>,
+    readiness: Arc<RwLock<Readiness>>,
+}
+
+/// Text front end for `/analyze`, which runs without the lock of the synthesis sessions
+struct FrontEnd {
+    jtalk: JTalk,
+    tokenizer: Tokenizer,
+}
+
+/// Models listed in `WARMUP_MODELS`, `*` for every model
+struct WarmupModels {
+    all: bool,
+    idents: HashSet<String>,
+}
+
+impl WarmupModels {
+    fn from_env() -> Self {
+        let models = env::var("WARMUP_MODELS").unwrap_or_default();
+        Self {
+            all: models.trim() == "*",
+            idents: models
+                .split(',')
+                .map(|x| x.trim().to_string())
+                .filter(|x| !x.is_empty() && x != "*")
+                .collect(),
+        }
+    }
+
+    fn contains(&self, ident: &str) -> bool {
+        self.all || self.idents.contains(ident)
+    }
+}
+
+/// Outcome of the warm-up at startup, see `/ready`
+enum Readiness {
+    WarmingUp,
+    Ready,
+    /// Idents whose warm-up failed
+    Failed(Vec<String>),
 }
 
 impl AppState {
     pub async fn new() -> anyhow::Result<Self> {
+        // Everything below that changes the audio of every model, for the audio cache keys
+        let mut settings = Sha256::new();
+        let mut add_setting = |name: &str, value: &[u8]| {
+            settings.update(name.as_bytes());
+            settings.update((value.len() as u64).to_le_bytes());
+            settings.update(value);
+        };
+        let bert = fs::read(env::var("BERT_MODEL_PATH")?).await?;
+        let tokenizer = fs::read(env::var("TOKENIZER_PATH")?).await?;
         let mut tts_model = TTSModelHolder::new(
-            &fs::read(env::var("BERT_MODEL_PATH")?).await?,
-            &fs::read(env::var("TOKENIZER_PATH")?).await?,
-            env::var("HOLDER_MAX_LOADED_MODElS")
-                .ok()
-                .and_then(|x| x.parse().ok()),
+            &bert,
+            &tokenizer,
+            env_parse("HOLDER_MAX_LOADED_MODElS")?,
+            session_config_from_env()?,
         )?;
-        let models = env::var("MODELS_PATH").unwrap_or("models".to_string());
-        let mut f = fs::read_dir(&models).await?;
-        let mut entries = vec![];
-        while let Ok(Some(e)) = f.next_entry().await {
-            let name = e.file_name().to_string_lossy().to_string();
-            if name.ends_with(".onnx") && name.starts_with("model_") {
-                let name_len = name.len();
-                let name = name.chars();
-                entries.push(
-                    name.collect::<Vec<_>>()[6..name_len - 5]
-                        .iter()
-                        .collect::<String>(),
-                );
-            } else if name.ends_with(".sbv2") {
-                let entry = &name[..name.len() - 5];
-                log::info!("Try loading: {entry}");
-                let sbv2_bytes = match fs::read(format!("{models}/{entry}.sbv2")).await {
-                    Ok(b) => b,
-                    Err(e) => {
-                        log::warn!("Error loading sbv2_bytes from file {entry}: {e}");
-                        continue;
-                    }
-                };
-                if let Err(e) = tts_model.load_sbv2file(entry, sbv2_bytes) {
-                    log::warn!("Error loading {entry}: {e}");
-                };
-                log::info!("Loaded: {entry}");
-            } else if name.ends_with(".aivmx") {
-                let entry = &name[..name.len() - 6];
-                log::info!("Try loading: {entry}");
-                let aivmx_bytes = match fs::read(format!("{models}/{entry}.aivmx")).await {
-                    Ok(b) => b,
-                    Err(e) => {
-                        log::warn!("Error loading aivmx bytes from file {entry}: {e}");
-                        continue;
-                    }
-                };
-                if let Err(e) = tts_model.load_aivmx(entry, aivmx_bytes) {
-                    log::error!("Error loading {entry}: {e}");
+        add_setting(
+            "BERT_MODEL_PATH,TOKENIZER_PATH",
+            &tts_model.bert_fingerprint(),
+        );
+        // The session has its own copy
+        drop(bert);
+        // Shares the dictionary of the holder's front end, and is configured the same below
+        let mut front_end = FrontEnd {
+            jtalk: JTalk {
+                jpreprocess: tts_model.jtalk.jpreprocess.clone(),
+                preprocessors: Default::default(),
+                fallback: Default::default(),
+            },
+            tokenizer: get_tokenizer(&tokenizer)?,
+        };
+        if let Some(path) = env_parse::<String>("ENGLISH_DICT_PATH")? {
+            let dict = fs::read(&path).await?;
+            add_setting("ENGLISH_DICT_PATH", &dict);
+            for jtalk in [&mut tts_model.jtalk, &mut front_end.jtalk] {
+                jtalk.preprocessors.english.load_overrides(&dict)?;
+            }
+            log::info!("Loaded English dictionary: {path}");
+        }
+        if let Some(modes) = env_parse::<String>("SYMBOL_MODES")? {
+            add_setting("SYMBOL_MODES", modes.as_bytes());
+            for jtalk in [&mut tts_model.jtalk, &mut front_end.jtalk] {
+                jtalk.preprocessors.symbols.set_modes_from_str(&modes)?;
+            }
+        }
+        if let Some(policy) = env_parse("FALLBACK_POLICY")? {
+            add_setting("FALLBACK_POLICY", format!("{policy:?}").as_bytes());
+            tts_model.jtalk.fallback = policy;
+            front_end.jtalk.fallback = policy;
+        }
+        if let Some(size) = env_parse("BERT_CACHE_SIZE")? {
+            tts_model.set_bert_cache_capacity(size);
+            if let Some(path) =
+                env_parse::<String>("BERT_CACHE_PATH")?.filter(|x| std::path::Path::new(x).exists())
+            {
+                match tts_model.load_bert_cache(&path) {
+                    Ok(()) => log::info!("Loaded BERT cache: {path}"),
+                    Err(e) => log::warn!("Error loading BERT cache {path}: {e}"),
                 }
-                log::info!("Loaded: {entry}");
             }
         }
-        for entry in entries {
-            log::info!("Try loading: {entry}");
-            let style_vectors_bytes =
-                match fs::read(format!("{models}/style_vectors_{entry}.json")).await {
-                    Ok(b) => b,
-                    Err(e) => {
-                        log::warn!("Error loading style_vectors_bytes from file {entry}: {e}");
-                        continue;
-                    }
-                };
-            let vits2_bytes = match fs::read(format!("{models}/model_{entry}.onnx")).await {
-                Ok(b) => b,
-                Err(e) => {
-                    log::warn!("Error loading vits2_bytes from file {entry}: {e}");
-                    continue;
+        if let Some(paths) = env_parse::<String>("PREPROCESS_RULES_PATH")? {
+            for path in paths.split(',').filter(|x| !x.is_empty()) {
+                let rules = fs::read(path).await?;
+                add_setting("PREPROCESS_RULES_PATH", &rules);
+                for jtalk in [&mut tts_model.jtalk, &mut front_end.jtalk] {
+                    jtalk.preprocessors.load_rules(&rules)?;
                 }
+                log::info!("Loaded preprocess rules: {path}");
+            }
+        }
+        let models = env_parse("MODELS_PATH")?.unwrap_or("models".to_string());
+        let registry = ModelRegistry::default();
+        // Absolute paths are recorded, which the watcher reports changes with
+        let mut f = fs::read_dir(fs::canonicalize(&models).await?).await?;
+        while let Ok(Some(e)) = f.next_entry().await {
+            let Some((ident, _)) = model_entry(&e.file_name().to_string_lossy()) else {
+                continue;
             };
-            if let Err(e) = tts_model.load(&entry, style_vectors_bytes, vits2_bytes) {
-                log::warn!("Error loading {entry}: {e}");
+            log::info!("Try loading: {ident}");
+            let path = e.path();
+            let loaded = match ModelFiles::read(&path).await {
+                Ok(files) => files.load(&mut tts_model, &registry, &ident, Some(path)),
+                Err(e) => Err(e),
             };
-            log::info!("Loaded: {entry}");
+            match loaded {
+                Ok(()) => log::info!("Loaded: {ident}"),
+                Err(e) => log::warn!("Error loading {ident}: {e}"),
+            }
         }
         Ok(Self {
             tts_model: Arc::new(Mutex::new(tts_model)),
+            front_end: Arc::new(front_end),
+            registry: Arc::new(registry),
+            admin: Arc::new(Admin::from_env()?),
+            cache: Arc::new(AudioCache::from_env(format!("{:x}", settings.finalize())).await?),
+            warmup_models: Arc::new(WarmupModels::from_env()),
+            readiness: Arc::new(RwLock::new(Readiness::WarmingUp)),
         })
     }
+
+    /// Warm up `ident` now and whenever it is loaded again, if it is listed in `WARMUP_MODELS`
+    ///
+    /// Models that are not in memory because of `HOLDER_MAX_LOADED_MODElS` are warmed up when loaded.
+    fn warmup_model(&self, tts_model: &mut TTSModelHolder, ident: &str) -> anyhow::Result<()> {
+        if !self.warmup_models.contains(ident) {
+            return Ok(());
+        }
+        log::info!("Warming up: {ident}");
+        tts_model.set_warmup(ident, true)?;
+        if !tts_model.warmup_model(ident)? {
+            log::warn!("{ident} is not in memory, warming it up when it is loaded");
+        }
+        self.registry.refresh(tts_model);
+        Ok(())
+    }
+
+    /// Warm up the models listed in `WARMUP_MODELS` and mark the server ready, unless one fails
+    async fn warmup(&self) {
+        let mut tts_model = self.tts_model.lock().await;
+        let mut failed = Vec::new();
+        for ident in tts_model.models() {
+            if let Err(e) = self.warmup_model(&mut tts_model, &ident) {
+                log::error!("Error warming up {ident}: {e}");
+                failed.push(ident);
+            }
+        }
+        let loaded = tts_model.models();
+        for ident in self.warmup_models.idents.iter() {
+            if !loaded.contains(ident) {
+                log::warn!("{ident} in WARMUP_MODELS is not loaded, warming it up when it is");
+            }
+        }
+        if failed.is_empty() {
+            *self.readiness.write().unwrap() = Readiness::Ready;
+            log::info!("Ready");
+        } else {
+            log::error!("Warm-up failed, not ready: {}", failed.join(", "));
+            *self.readiness.write().unwrap() = Readiness::Failed(failed);
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/ready",
+    responses(
+        (status = 200, description = "Models are loaded and warmed up"),
+        (status = 503, description = "Warm-up is still running, or failed for the models listed")
+    )
+)]
+async fn ready(State(state): State<AppState>) -> impl IntoResponse {
+    match &*state.readiness.read().unwrap() {
+        Readiness::Ready => (StatusCode::OK, "ready".to_string()),
+        Readiness::WarmingUp => (StatusCode::SERVICE_UNAVAILABLE, "warming up".to_string()),
+        Readiness::Failed(idents) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("warm-up failed: {}", idents.join(", ")),
+        ),
+    }
+}
+
+fn app(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(|| async { "Hello, World!" }))
+        .route("/synthesize", post(synthesize).get(synthesize_get))
+        .route("/synthesize_phones", post(synthesize_phones))
+        .route("/models", get(models).post(admin::load_model))
+        .route(
+            "/models/{ident}",
+            get(model_info).delete(admin::unload_model),
+        )
+        .route("/models/{ident}/speakers", get(speakers))
+        .route("/models/{ident}/reload", post(admin::reload_model))
+        .route(
+            "/models/{ident}/pin",
+            post(admin::pin_model).delete(admin::unpin_model),
+        )
+        .route("/analyze", post(analyze))
+        .route("/ready", get(ready))
+        .with_state(state)
+        .merge(Scalar::with_url("/docs", ApiDoc::openapi()))
+}
+
+async fn shutdown_signal() {
+    tokio::signal::ctrl_c().await.ok();
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenvy::dotenv_override().ok();
     env_logger::init();
-    let app = Router::new()
-        .route("/", get(|| async { "Hello, World!" }))
-        .route("/synthesize", post(synthesize))
-        .route("/models", get(models))
-        .with_state(AppState::new().await?)
-        .merge(Scalar::with_url("/docs", ApiDoc::openapi()));
-    let addr = env::var("ADDR").unwrap_or("0.0.0.0:3000".to_string());
+    let state = AppState::new().await?;
+    if env_parse("WATCH_MODELS")?.unwrap_or(false) {
+        let models = env_parse("MODELS_PATH")?.unwrap_or("models".to_string());
+        watcher::spawn(state.clone(), models.into())?;
+    }
+    tokio::spawn({
+        let state = state.clone();
+        async move { state.warmup().await }
+    });
+    let app = app(state.clone());
+    let addr = env_parse("ADDR")?.unwrap_or("0.0.0.0:3000".to_string());
     let listener = tokio::net::TcpListener::bind(&addr).await?;
     log::info!("Listening on {addr}");
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+    if let Some(path) = env_parse::<String>("BERT_CACHE_PATH")? {
+        let tts_model = state.tts_model.lock().await;
+        if tts_model.bert_cache_stats().capacity > 0 {
+            tts_model.save_bert_cache(&path)?;
+            log::info!("Saved BERT cache: {path}");
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,136 @@
+use sbv2_core::speaker::Speaker;
+use sbv2_core::tts::TTSModelHolder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// Files a model was loaded from
+#[derive(Clone, Copy, Deserialize, Serialize, ToSchema)]
+pub enum ModelFormat {
+    #[serde(rename = "sbv2")]
+    Sbv2,
+    #[serde(rename = "aivmx")]
+    Aivmx,
+    /// `model_{ident}.onnx` and `style_vectors_{ident}.json`
+    #[serde(rename = "onnx+json")]
+    OnnxJson,
+}
+
+#[derive(Clone, Serialize, ToSchema)]
+pub struct SpeakerInfo {
+    id: i64,
+    name: String,
+}
+
+impl From<Speaker> for SpeakerInfo {
+    fn from(value: Speaker) -> Self {
+        SpeakerInfo {
+            id: value.id,
+            name: value.name,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, ToSchema)]
+pub struct ModelDetails {
+    ident: String,
+    format: ModelFormat,
+    /// Whether the VITS2 session is in memory, models evicted by `HOLDER_MAX_LOADED_MODElS` are not
+    loaded: bool,
+    /// Whether the model is never evicted, see `POST /models/{ident}/pin`
+    pinned: bool,
+    /// Whether the model is warmed up when loaded, see `WARMUP_MODELS`
+    pub warmup: bool,
+    style_count: usize,
+    /// Style names in style id order, empty when the model has no config
+    style_names: Vec<String>,
+    /// Empty when the model has no config
+    pub speakers: Vec<SpeakerInfo>,
+    /// Sample rate of the synthesized WAV files
+    sample_rate: u32,
+    /// Total size of the model files in bytes
+    file_size: u64,
+    /// Unix time the model was loaded at, in seconds
+    loaded_at: u64,
+    /// Time loading the model took, in milliseconds
+    load_time_ms: u64,
+    /// File the model is reloaded from, none for uploaded models
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    /// Hash of the model files, which tells cached audio of this model from that of earlier ones
+    #[serde(skip)]
+    pub digest: String,
+}
+
+/// Files a model has just been loaded from
+pub struct LoadedFiles {
+    pub format: ModelFormat,
+    pub file_size: u64,
+    pub digest: String,
+    pub path: Option<PathBuf>,
+}
+
+/// Model details kept outside the `TTSModelHolder` mutex, so they can be read while synthesis runs
+#[derive(Default)]
+pub struct ModelRegistry {
+    models: RwLock<HashMap<String, ModelDetails>>,
+}
+
+impl ModelRegistry {
+    pub fn get(&self, ident: &str) -> Option<ModelDetails> {
+        self.models.read().unwrap().get(ident).cloned()
+    }
+
+    /// Record a model `tts_model` has just loaded
+    pub fn insert(
+        &self,
+        tts_model: &TTSModelHolder,
+        ident: &str,
+        files: LoadedFiles,
+        load_time: Duration,
+    ) -> anyhow::Result<()> {
+        let info = tts_model.model_info(ident)?;
+        let details = ModelDetails {
+            ident: ident.to_string(),
+            format: files.format,
+            loaded: info.loaded,
+            pinned: info.pinned,
+            warmup: info.warmup,
+            style_count: info.style_count,
+            style_names: info.style_names,
+            speakers: info.speakers.into_iter().map(SpeakerInfo::from).collect(),
+            sample_rate: info.sample_rate,
+            file_size: files.file_size,
+            loaded_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|x| x.as_secs())
+                .unwrap_or_default(),
+            load_time_ms: load_time.as_millis() as u64,
+            path: files.path,
+            digest: files.digest,
+        };
+        self.models
+            .write()
+            .unwrap()
+            .insert(ident.to_string(), details);
+        Ok(())
+    }
+
+    pub fn remove(&self, ident: &str) -> Option<ModelDetails> {
+        self.models.write().unwrap().remove(ident)
+    }
+
+    /// Update the load state after `tts_model` may have loaded, evicted or pinned models
+    pub fn refresh(&self, tts_model: &TTSModelHolder) {
+        for (ident, details) in self.models.write().unwrap().iter_mut() {
+            if let Ok(info) = tts_model.model_info(ident.as_str()) {
+                details.loaded = info.loaded;
+                details.pinned = info.pinned;
+                details.warmup = info.warmup;
+            }
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use axum::body::{to_bytes, Body};
+use axum::http::{header, HeaderMap, Request, StatusCode};
+use axum::response::Response;
+use sbv2_core::backend::{mock, Backend};
+use sbv2_core::jtalk::JTalk;
+use sbv2_core::model::SessionConfig;
+use sbv2_core::tokenizer::get_tokenizer;
+use sbv2_core::tts::TTSModelHolder;
+use std::sync::{Arc, RwLock};
+use tokio::sync::Mutex;
+use tower::ServiceExt;
+
+use crate::admin::Admin;
+use crate::cache::AudioCache;
+use crate::loader::ModelFiles;
+use crate::registry::ModelRegistry;
+use crate::{app, AppState, FrontEnd, Readiness, WarmupModels};
+
+/// State with the mock model `mock` loaded
+async fn state() -> AppState {
+    let config = SessionConfig {
+        backend: Backend::Mock,
+        ..Default::default()
+    };
+    let mut tts_model =
+        TTSModelHolder::new(Vec::new(), mock::tokenizer_json(), None, config).unwrap();
+    let registry = ModelRegistry::default();
+    ModelFiles::OnnxJson {
+        style_vectors: mock::style_vectors_json(1),
+        vits2: Vec::new(),
+        config: None,
+    }
+    .load(&mut tts_model, &registry, "mock", None)
+    .unwrap();
+    let front_end = FrontEnd {
+        jtalk: JTalk {
+            jpreprocess: tts_model.jtalk.jpreprocess.clone(),
+            preprocessors: Default::default(),
+            fallback: Default::default(),
+        },
+        tokenizer: get_tokenizer(mock::tokenizer_json()).unwrap(),
+    };
+    AppState {
+        tts_model: Arc::new(Mutex::new(tts_model)),
+        front_end: Arc::new(front_end),
+        registry: Arc::new(registry),
+        admin: Arc::new(Admin::from_env().unwrap()),
+        cache: Arc::new(AudioCache::from_env(String::new()).await.unwrap()),
+        warmup_models: Arc::new(WarmupModels::from_env()),
+        readiness: Arc::new(RwLock::new(Readiness::Ready)),
+    }
+}
+
+async fn synthesize(state: &AppState, body: serde_json::Value, etag: Option<&str>) -> Response {
+    let mut request = Request::post("/synthesize").header(header::CONTENT_TYPE, "application/json");
+    if let Some(etag) = etag {
+        request = request.header(header::IF_NONE_MATCH, etag);
+    }
+    app(state.clone())
+        .oneshot(request.body(Body::from(body.to_string())).unwrap())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_synthesize() {
+    let state = state().await;
+    let request = serde_json::json!({"ident": "mock", "text": "コンニチワ'", "is_kana": true});
+    let response = synthesize(&state, request.clone(), None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "audio/wav");
+    let etag = response.headers()[header::ETAG]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let wav = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert!(hound::WavReader::new(wav.as_ref()).unwrap().duration() > 0);
+
+    let response = synthesize(&state, request, Some(&etag)).await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    // Another request has another ETag
+    let request = serde_json::json!({"ident": "mock", "text": "コ'レ", "is_kana": true});
+    let response = synthesize(&state, request, Some(&etag)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_synthesize_errors() {
+    let state = state().await;
+    let request = serde_json::json!({"ident": "missing", "text": "コ'レ", "is_kana": true});
+    let response = synthesize(&state, request, None).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let request = serde_json::json!({
+        "ident": "mock",
+        "text": "コ'レ",
+        "is_kana": true,
+        "speaker_name": "missing",
+    });
+    let response = synthesize(&state, request, None).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_replace_failure_keeps_model() {
+    let state = state().await;
+    let digest = state.registry.get("mock").unwrap().digest;
+    let invalid = ModelFiles::OnnxJson {
+        style_vectors: b"not json".to_vec(),
+        vits2: Vec::new(),
+        config: None,
+    };
+    {
+        let mut tts_model = state.tts_model.lock().await;
+        assert!(invalid
+            .replace(&mut tts_model, &state.registry, "mock", None)
+            .is_err());
+    }
+    assert_eq!(state.registry.get("mock").unwrap().digest, digest);
+    let request = serde_json::json!({"ident": "mock", "text": "コ'レ", "is_kana": true});
+    let response = synthesize(&state, request, None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_analyze_without_synthesis_lock() {
+    let state = state().await;
+    let _lock = state.tts_model.lock().await;
+    let request = Request::post("/analyze")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            serde_json::json!({"text": "テスト"}).to_string(),
+        ))
+        .unwrap();
+    let response = tokio::time::timeout(
+        std::time::Duration::from_secs(30),
+        app(state.clone()).oneshot(request),
+    )
+    .await
+    .expect("analyze waited for the synthesis lock")
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_cache_single_flight() {
+    let cache = AudioCache::from_env(String::new()).await.unwrap();
+    let runs = std::sync::atomic::AtomicUsize::new(0);
+    let headers = HeaderMap::new();
+    let respond = || {
+        cache.respond("key", &headers, async {
+            runs.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            Ok(b"audio".to_vec())
+        })
+    };
+    let (a, b) = tokio::join!(respond(), respond());
+    for response in [a.unwrap(), b.unwrap()] {
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), b"audio");
+    }
+    assert_eq!(runs.load(std::sync::atomic::Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn test_cache_key_settings() {
+    let a = AudioCache::from_env("a".to_string()).await.unwrap();
+    let b = AudioCache::from_env("b".to_string()).await.unwrap();
+    let request = serde_json::json!({"text": "コ'レ"});
+    assert_ne!(
+        a.key("synthesize/wav", "digest", &request).unwrap(),
+        b.key("synthesize/wav", "digest", &request).unwrap()
+    );
+}
@@ -0,0 +1,232 @@
+use notify::event::{AccessKind, AccessMode, MetadataKind, ModifyKind};
+use notify::{Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+use crate::loader::{model_entry, ModelFiles};
+use crate::{env_parse, AppState};
+
+/// Ident and main file of the model a file in `MODELS_PATH` belongs to
+///
+/// `style_vectors_{ident}.json` and `config_{ident}.json` belong to `model_{ident}.onnx`.
+fn changed_model(path: &Path) -> Option<(String, PathBuf)> {
+    let file_name = path.file_name()?.to_string_lossy();
+    if let Some((ident, _)) = model_entry(&file_name) {
+        return Some((ident, path.to_path_buf()));
+    }
+    let ident = file_name
+        .strip_prefix("style_vectors_")
+        .or_else(|| file_name.strip_prefix("config_"))?
+        .strip_suffix(".json")?;
+    Some((
+        ident.to_string(),
+        path.with_file_name(format!("model_{ident}.onnx")),
+    ))
+}
+
+/// Whether an event can change the contents of a file, reads by the loader itself are not
+fn is_change(kind: &EventKind) -> bool {
+    match kind {
+        EventKind::Create(_) | EventKind::Remove(_) => true,
+        // Polling reports writes as a new modification time
+        EventKind::Modify(ModifyKind::Metadata(kind)) => *kind == MetadataKind::WriteTime,
+        EventKind::Modify(_) => true,
+        EventKind::Access(AccessKind::Close(AccessMode::Write)) => true,
+        _ => false,
+    }
+}
+
+fn watch(
+    models: &Path,
+    sender: UnboundedSender<PathBuf>,
+    poll: bool,
+    poll_interval: Duration,
+) -> notify::Result<Box<dyn Watcher + Send>> {
+    let handler = move |event: notify::Result<Event>| match event {
+        Ok(event) if is_change(&event.kind) => {
+            for path in event.paths {
+                let _ = sender.send(path);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => log::warn!("Error watching models: {e}"),
+    };
+    let poll_watcher = |handler| -> notify::Result<Box<dyn Watcher + Send>> {
+        let mut watcher = PollWatcher::new(
+            handler,
+            notify::Config::default().with_poll_interval(poll_interval),
+        )?;
+        watcher.watch(models, RecursiveMode::NonRecursive)?;
+        Ok(Box::new(watcher))
+    };
+    if poll {
+        return poll_watcher(handler);
+    }
+    let recommended = RecommendedWatcher::new(handler.clone(), notify::Config::default()).and_then(
+        |mut watcher| {
+            watcher.watch(models, RecursiveMode::NonRecursive)?;
+            Ok(watcher)
+        },
+    );
+    match recommended {
+        Ok(watcher) => Ok(Box::new(watcher)),
+        // e.g. the inotify watch limit is reached
+        Err(e) => {
+            log::warn!("Error watching models, falling back to polling: {e}");
+            poll_watcher(handler)
+        }
+    }
+}
+
+/// Load, reload or unload `ident` to match the files on disk
+async fn sync_model(state: &AppState, ident: &str, path: PathBuf) -> anyhow::Result<()> {
+    let loaded_from = state.registry.get(ident).map(|x| x.path);
+    if !path.exists() {
+        if loaded_from == Some(Some(path)) {
+            let mut tts_model = state.tts_model.lock().await;
+            tts_model.unload(ident);
+            state.registry.remove(ident);
+            log::info!("Unloaded: {ident}");
+        }
+        return Ok(());
+    }
+    if loaded_from
+        .as_ref()
+        .is_some_and(|loaded_from| loaded_from.as_ref() != Some(&path))
+    {
+        log::warn!(
+            "{ident} is loaded from elsewhere, ignoring {}",
+            path.display()
+        );
+        return Ok(());
+    }
+    // Read the files before taking the lock, so synthesis only waits for the session
+    let files = ModelFiles::read(&path).await?;
+    let mut tts_model = state.tts_model.lock().await;
+    if loaded_from.is_some() {
+        // The loaded model keeps serving if the new files fail to load
+        files.replace(&mut tts_model, &state.registry, ident, Some(path))?;
+        log::info!("Reloaded: {ident}");
+    } else {
+        files.load(&mut tts_model, &state.registry, ident, Some(path))?;
+        log::info!("Loaded: {ident}");
+        if let Err(e) = state.warmup_model(&mut tts_model, ident) {
+            log::error!("Error warming up {ident}: {e}");
+        }
+    }
+    Ok(())
+}
+
+/// Keep the loaded models in sync with `models`
+///
+/// A model is synced once its files have not changed for `WATCH_DEBOUNCE_MS`, so that
+/// files still being copied are not loaded. `WATCH_POLL=true` polls every
+/// `WATCH_POLL_INTERVAL_MS` instead of using inotify, e.g. for network volumes.
+pub fn spawn(state: AppState, models: PathBuf) -> anyhow::Result<()> {
+    let debounce = Duration::from_millis(env_parse("WATCH_DEBOUNCE_MS")?.unwrap_or(2000));
+    let poll_interval = Duration::from_millis(env_parse("WATCH_POLL_INTERVAL_MS")?.unwrap_or(2000));
+    let models = std::fs::canonicalize(models)?;
+    let (sender, mut receiver) = unbounded_channel();
+    let watcher = watch(
+        &models,
+        sender,
+        env_parse("WATCH_POLL")?.unwrap_or(false),
+        poll_interval,
+    )?;
+    log::info!("Watching models: {}", models.display());
+    tokio::spawn(async move {
+        // Dropping the watcher stops the events
+        let _watcher = watcher;
+        let mut pending: HashMap<PathBuf, (String, Instant)> = HashMap::new();
+        loop {
+            tokio::select! {
+                path = receiver.recv() => {
+                    let Some(path) = path else {
+                        break;
+                    };
+                    if let Some((ident, model_path)) = changed_model(&path) {
+                        // Copies emit an event for every write, log the first one only
+                        if pending.insert(model_path, (ident, Instant::now())).is_none() {
+                            log::info!("Model file changed: {}", path.display());
+                        }
+                    }
+                }
+                _ = tokio::time::sleep(debounce / 4) => {}
+            }
+            let settled: Vec<PathBuf> = pending
+                .iter()
+                .filter(|(_, (_, changed))| changed.elapsed() >= debounce)
+                .map(|(path, _)| path.clone())
+                .collect();
+            for path in settled {
+                let Some((ident, _)) = pending.remove(&path) else {
+                    continue;
+                };
+                if let Err(e) = sync_model(&state, &ident, path).await {
+                    log::warn!("Error syncing {ident}: {e}");
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, DataChange, RemoveKind};
+
+    #[test]
+    fn test_changed_model() {
+        let model = |ident: &str, path: &str| Some((ident.to_string(), PathBuf::from(path)));
+        assert_eq!(
+            changed_model(Path::new("models/model_a.onnx")),
+            model("a", "models/model_a.onnx")
+        );
+        assert_eq!(
+            changed_model(Path::new("models/style_vectors_a.json")),
+            model("a", "models/model_a.onnx")
+        );
+        assert_eq!(
+            changed_model(Path::new("models/config_a.json")),
+            model("a", "models/model_a.onnx")
+        );
+        assert_eq!(
+            changed_model(Path::new("models/b.sbv2")),
+            model("b", "models/b.sbv2")
+        );
+        assert_eq!(
+            changed_model(Path::new("models/c.aivmx")),
+            model("c", "models/c.aivmx")
+        );
+        assert_eq!(changed_model(Path::new("models/tokenizer.json")), None);
+        assert_eq!(changed_model(Path::new("models/deberta.onnx")), None);
+        assert_eq!(changed_model(Path::new("models/config_a.json.tmp")), None);
+    }
+
+    #[test]
+    fn test_is_change() {
+        assert!(is_change(&EventKind::Create(CreateKind::File)));
+        assert!(is_change(&EventKind::Remove(RemoveKind::File)));
+        assert!(is_change(&EventKind::Modify(ModifyKind::Data(
+            DataChange::Content
+        ))));
+        assert!(is_change(&EventKind::Modify(ModifyKind::Metadata(
+            MetadataKind::WriteTime
+        ))));
+        assert!(is_change(&EventKind::Access(AccessKind::Close(
+            AccessMode::Write
+        ))));
+        assert!(!is_change(&EventKind::Modify(ModifyKind::Metadata(
+            MetadataKind::AccessTime
+        ))));
+        assert!(!is_change(&EventKind::Access(AccessKind::Close(
+            AccessMode::Read
+        ))));
+        assert!(!is_change(&EventKind::Access(AccessKind::Open(
+            AccessMode::Any
+        ))));
+    }
+}
@@ -0,0 +1,111 @@
+use pyo3::prelude::*;
+
+/// Word class
+///
+/// 形態素解析で得られた単語を表すクラス
+#[pyclass(get_all)]
+#[derive(Clone)]
+pub struct Word {
+    pub surface: String,
+    pub pos: String,
+    pub pos_group1: String,
+    pub pos_group2: String,
+    pub pos_group3: String,
+    pub ctype: String,
+    pub cform: String,
+    pub orig: String,
+    pub read: String,
+    pub pronunciation: String,
+    pub accent: i32,
+    pub mora_size: i32,
+    pub chain_rule: String,
+    pub chain_flag: i32,
+}
+
+/// AccentPhrase class
+///
+/// アクセント句を表すクラス
+#[pyclass(get_all)]
+#[derive(Clone)]
+pub struct AccentPhrase {
+    pub moras: Vec<String>,
+    pub accent: usize,
+    pub is_interrogative: bool,
+    pub pause: bool,
+}
+
+/// TextAnalysis class
+///
+/// テキスト解析の各段階の結果を表すクラス
+#[pyclass(get_all)]
+pub struct TextAnalysis {
+    pub normalized_text: String,
+    pub words: Vec<Word>,
+    pub accent_phrases: Vec<AccentPhrase>,
+    pub kana: String,
+    pub phones: Vec<String>,
+    pub tones: Vec<i32>,
+    pub word2ph: Vec<i32>,
+    pub bert_text: String,
+    pub token_ids: Vec<i64>,
+    json: String,
+}
+
+impl TextAnalysis {
+    pub fn new(analysis: sbv2_core::analysis::TextAnalysis) -> anyhow::Result<Self> {
+        let json = analysis.to_json()?;
+        Ok(Self {
+            normalized_text: analysis.normalized_text,
+            words: analysis
+                .words
+                .into_iter()
+                .map(|w| Word {
+                    surface: w.surface,
+                    pos: w.pos,
+                    pos_group1: w.pos_group1,
+                    pos_group2: w.pos_group2,
+                    pos_group3: w.pos_group3,
+                    ctype: w.ctype,
+                    cform: w.cform,
+                    orig: w.orig,
+                    read: w.read,
+                    pronunciation: w.pronunciation,
+                    accent: w.accent,
+                    mora_size: w.mora_size,
+                    chain_rule: w.chain_rule,
+                    chain_flag: w.chain_flag,
+                })
+                .collect(),
+            accent_phrases: analysis
+                .accent_phrases
+                .into_iter()
+                .map(|p| AccentPhrase {
+                    moras: p.moras.into_iter().map(|m| m.kana).collect(),
+                    accent: p.accent,
+                    is_interrogative: p.is_interrogative,
+                    pause: p.pause,
+                })
+                .collect(),
+            kana: analysis.kana,
+            phones: analysis.phones,
+            tones: analysis.tones,
+            word2ph: analysis.word2ph,
+            bert_text: analysis.bert_text,
+            token_ids: analysis.token_ids,
+            json,
+        })
+    }
+}
+
+#[pymethods]
+impl TextAnalysis {
+    /// 解析結果をJSON文字列として取得する
+    ///
+    /// Returns
+    /// -------
+    /// json : str
+    ///     JSON文字列
+    fn to_json(&self) -> String {
+        self.json.clone()
+    }
+}
@@ -1,4 +1,5 @@
 use pyo3::prelude::*;
+pub mod analysis;
 mod sbv2;
 pub mod style;
 
@@ -7,5 +8,8 @@ pub mod style;
 fn sbv2_bindings(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<sbv2::TTSModel>()?;
     m.add_class::<style::StyleVector>()?;
+    m.add_class::<analysis::TextAnalysis>()?;
+    m.add_class::<analysis::Word>()?;
+    m.add_class::<analysis::AccentPhrase>()?;
     Ok(())
 }
@@ -1,11 +1,30 @@
 use pyo3::prelude::*;
 use pyo3::types::PyBytes;
+use sbv2_core::model::SessionConfig;
 use sbv2_core::tts::{SynthesizeOptions, TTSModelHolder};
 
+use crate::analysis::TextAnalysis;
 use crate::style::StyleVector;
 
 use std::fs;
 
+fn session_config(
+    intra_threads: Option<usize>,
+    inter_threads: Option<usize>,
+    backend: Option<String>,
+) -> anyhow::Result<SessionConfig> {
+    let default = SessionConfig::default();
+    Ok(SessionConfig {
+        backend: match backend {
+            Some(backend) => backend.parse()?,
+            None => default.backend,
+        },
+        intra_threads: intra_threads.unwrap_or(default.intra_threads),
+        inter_threads: inter_threads.unwrap_or(default.inter_threads),
+        ..default
+    })
+}
+
 /// TTSModel class
 ///
 /// 音声合成するために使うクラス
@@ -16,6 +35,14 @@ use std::fs;
 ///     BERTモデルのバイナリデータ
 /// tokenizer_bytes : bytes
 ///     トークナイザーのバイナリデータ
+/// max_loaded_models: int | None
+///     同時にVRAMに存在するモデルの数
+/// intra_threads: int | None
+///     演算子内で使うスレッド数 (省略時は物理コア数)
+/// inter_threads: int | None
+///     演算子間で使うスレッド数 (省略時は物理コア数)
+/// backend: str | None
+///     推論バックエンド ("ort", "tract", テスト用の"mock")
 #[pyclass]
 pub struct TTSModel {
     pub model: TTSModelHolder,
@@ -23,15 +50,23 @@ pub struct TTSModel {
 
 #[pymethods]
 impl TTSModel {
-    #[pyo3(signature = (bert_model_bytes, tokenizer_bytes, max_loaded_models=None))]
+    #[pyo3(signature = (bert_model_bytes, tokenizer_bytes, max_loaded_models=None, intra_threads=None, inter_threads=None, backend=None))]
     #[new]
     fn new(
         bert_model_bytes: Vec<u8>,
         tokenizer_bytes: Vec<u8>,
         max_loaded_models: Option<usize>,
+        intra_threads: Option<usize>,
+        inter_threads: Option<usize>,
+        backend: Option<String>,
     ) -> anyhow::Result<Self> {
         Ok(Self {
-            model: TTSModelHolder::new(bert_model_bytes, tokenizer_bytes, max_loaded_models)?,
+            model: TTSModelHolder::new(
+                bert_model_bytes,
+                tokenizer_bytes,
+                max_loaded_models,
+                session_config(intra_threads, inter_threads, backend)?,
+            )?,
         })
     }
 
@@ -45,18 +80,28 @@ impl TTSModel {
     ///     トークナイザーのパス
     /// max_loaded_models: int | None
     ///     同時にVRAMに存在するモデルの数
-    #[pyo3(signature = (bert_model_path, tokenizer_path, max_loaded_models=None))]
+    /// intra_threads: int | None
+    ///     演算子内で使うスレッド数 (省略時は物理コア数)
+    /// inter_threads: int | None
+    ///     演算子間で使うスレッド数 (省略時は物理コア数)
+    /// backend: str | None
+    ///     推論バックエンド ("ort", "tract", テスト用の"mock")
+    #[pyo3(signature = (bert_model_path, tokenizer_path, max_loaded_models=None, intra_threads=None, inter_threads=None, backend=None))]
     #[staticmethod]
     fn from_path(
         bert_model_path: String,
         tokenizer_path: String,
         max_loaded_models: Option<usize>,
+        intra_threads: Option<usize>,
+        inter_threads: Option<usize>,
+        backend: Option<String>,
     ) -> anyhow::Result<Self> {
         Ok(Self {
             model: TTSModelHolder::new(
                 fs::read(bert_model_path)?,
                 fs::read(tokenizer_path)?,
                 max_loaded_models,
+                session_config(intra_threads, inter_threads, backend)?,
             )?,
         })
     }
@@ -91,6 +136,27 @@ impl TTSModel {
         Ok(())
     }
 
+    /// モデルのウォームアップを行う
+    ///
+    /// 短いダミーの音声合成を実行して、最初の合成が遅くならないようにする
+    ///
+    /// Parameters
+    /// ----------
+    /// ident : str
+    ///     識別子
+    /// keep : bool
+    ///     モデルが再読み込みされたときにもウォームアップを行うか
+    ///
+    /// Returns
+    /// -------
+    /// warmed_up : bool
+    ///     ウォームアップを行ったか (メモリ上にないモデルはスキップされる)
+    #[pyo3(signature = (ident, keep=true))]
+    fn warmup(&mut self, ident: String, keep: bool) -> anyhow::Result<bool> {
+        self.model.set_warmup(ident.as_str(), keep)?;
+        Ok(self.model.warmup_model(ident)?)
+    }
+
     /// スタイルベクトルを取得する
     ///
     /// Parameters
@@ -117,6 +183,116 @@ impl TTSModel {
         ))
     }
 
+    /// 英単語の読みを登録する
+    ///
+    /// Parameters
+    /// ----------
+    /// word : str
+    ///     英単語 (小文字で登録すると大文字・小文字を区別しない)
+    /// kana : str
+    ///     読み (カタカナ)
+    fn add_english_word(&mut self, word: String, kana: String) {
+        self.model.jtalk.preprocessors.english.insert(&word, &kana);
+    }
+
+    /// パスから前処理ルールファイルを読み込む
+    ///
+    /// Parameters
+    /// ----------
+    /// rules_path : str
+    ///     ルールファイル (JSON) のパス
+    fn load_preprocess_rules_from_path(&mut self, rules_path: String) -> anyhow::Result<()> {
+        self.model.load_preprocess_rules(fs::read(rules_path)?)?;
+        Ok(())
+    }
+
+    /// 記号・絵文字の読み方を設定する
+    ///
+    /// Parameters
+    /// ----------
+    /// modes : str
+    ///     `emoji=read,decoration=pause` のような設定
+    ///     (分類: emoji, decoration, math, sign, arrow / 読み方: read, drop, pause)
+    fn set_symbol_modes(&mut self, modes: String) -> anyhow::Result<()> {
+        self.model
+            .jtalk
+            .preprocessors
+            .symbols
+            .set_modes_from_str(&modes)?;
+        Ok(())
+    }
+
+    /// 読めない単語の扱いを設定する
+    ///
+    /// Parameters
+    /// ----------
+    /// policy : str
+    ///     skip (単語を飛ばす), kana (表記をカナで読む), fail (エラーにする) のいずれか
+    fn set_fallback_policy(&mut self, policy: String) -> anyhow::Result<()> {
+        self.model.jtalk.fallback = policy.parse()?;
+        Ok(())
+    }
+
+    /// BERTの出力のキャッシュ件数を設定する
+    ///
+    /// Parameters
+    /// ----------
+    /// capacity : int
+    ///     キャッシュする件数 (0で無効)
+    fn set_bert_cache_capacity(&mut self, capacity: usize) {
+        self.model.set_bert_cache_capacity(capacity);
+    }
+
+    /// BERTの出力のキャッシュの統計を取得する
+    ///
+    /// Returns
+    /// -------
+    /// stats : tuple[int, int, int, int]
+    ///     ヒット数、ミス数、件数、最大件数
+    fn bert_cache_stats(&self) -> (u64, u64, usize, usize) {
+        let stats = self.model.bert_cache_stats();
+        (stats.hits, stats.misses, stats.len, stats.capacity)
+    }
+
+    /// BERTの出力のキャッシュをファイルに保存する
+    ///
+    /// Parameters
+    /// ----------
+    /// path : str
+    ///     保存先のパス
+    fn save_bert_cache(&self, path: String) -> anyhow::Result<()> {
+        self.model.save_bert_cache(path)?;
+        Ok(())
+    }
+
+    /// ファイルからBERTの出力のキャッシュを読み込む
+    ///
+    /// 同じBERTモデルとトークナイザーで保存したキャッシュのみ読み込める
+    ///
+    /// Parameters
+    /// ----------
+    /// path : str
+    ///     キャッシュファイルのパス
+    fn load_bert_cache(&mut self, path: String) -> anyhow::Result<()> {
+        self.model.load_bert_cache(path)?;
+        Ok(())
+    }
+
+    /// テキスト解析の各段階の結果を取得する
+    ///
+    /// Parameters
+    /// ----------
+    /// text : str
+    ///     テキスト
+    ///
+    /// Returns
+    /// -------
+    /// analysis : TextAnalysis
+    ///     正規化後のテキスト、単語、アクセント句、音素、トークンID
+    fn analyze(&self, text: String) -> anyhow::Result<TextAnalysis> {
+        TextAnalysis::new(self.model.analyze_text(&text)?)
+    }
+
     /// テキストから音声を合成する
     ///
     /// Parameters
@@ -131,11 +307,18 @@ impl TTSModel {
     ///     SDP比率
     /// length_scale : float
     ///     音声の長さのスケール
+    /// assist_text : str | None
+    ///     感情表現の補助に使うテキスト
+    /// assist_text_weight : float
+    ///     補助テキストの重み
+    /// pipeline : bool
+    ///     複数行のテキストで、次の文のBERTを現在の文の合成と並行して実行するかどうか
     ///
     /// Returns
     /// -------
     /// voice_data : bytes
     ///     音声データ
+    #[pyo3(signature = (text, ident, style_id, speaker_id, sdp_ratio, length_scale, assist_text=None, assist_text_weight=0.7, pipeline=false))]
     #[allow(clippy::too_many_arguments)]
     fn synthesize<'p>(
         &'p mut self,
@@ -146,6 +329,9 @@ impl TTSModel {
         speaker_id: i64,
         sdp_ratio: f32,
         length_scale: f32,
+        assist_text: Option<String>,
+        assist_text_weight: f32,
+        pipeline: bool,
     ) -> anyhow::Result<Bound<'p, PyBytes>> {
         let data = self.model.easy_synthesize(
             ident.as_str(),
@@ -155,6 +341,9 @@ impl TTSModel {
             SynthesizeOptions {
                 sdp_ratio,
                 length_scale,
+                assist_text,
+                assist_text_weight,
+                pipeline,
                 ..Default::default()
             },
         )?;
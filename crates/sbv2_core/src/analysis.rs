@@ -0,0 +1,128 @@
+use crate::error::Result;
+use crate::kana::AccentPhrase;
+use crate::{jtalk, kana, tokenizer, tts_util};
+use serde::Serialize;
+use tokenizers::Tokenizer;
+
+/// A word as analyzed by jpreprocess
+///
+/// # Fields
+/// - `surface`: Surface form
+/// - `pos`, `pos_group1`, `pos_group2`, `pos_group3`: Part of speech
+/// - `ctype`, `cform`: Conjugation type and form
+/// - `orig`: Base form
+/// - `read`, `pronunciation`: Reading and pronunciation in katakana
+/// - `accent`: Accent nucleus position, 0 for heiban
+/// - `mora_size`: Number of moras
+/// - `chain_rule`, `chain_flag`: Accent phrase chaining
+#[derive(Debug, Clone, Serialize)]
+pub struct WordAnalysis {
+    pub surface: String,
+    pub pos: String,
+    pub pos_group1: String,
+    pub pos_group2: String,
+    pub pos_group3: String,
+    pub ctype: String,
+    pub cform: String,
+    pub orig: String,
+    pub read: String,
+    pub pronunciation: String,
+    pub accent: i32,
+    pub mora_size: i32,
+    pub chain_rule: String,
+    pub chain_flag: i32,
+}
+
+impl WordAnalysis {
+    pub(crate) fn from_njd(features: &str) -> Self {
+        let parts: Vec<&str> = features.split(',').collect();
+        let part = |i: usize| parts.get(i).copied().unwrap_or("*").to_string();
+        let (accent, mora_size) = parts
+            .get(10)
+            .and_then(|x| x.split_once('/'))
+            .map(|(accent, mora_size)| {
+                (accent.parse().unwrap_or(0), mora_size.parse().unwrap_or(0))
+            })
+            .unwrap_or((0, 0));
+        WordAnalysis {
+            surface: part(0),
+            pos: part(1),
+            pos_group1: part(2),
+            pos_group2: part(3),
+            pos_group3: part(4),
+            ctype: part(5),
+            cform: part(6),
+            orig: part(7),
+            read: part(8),
+            pronunciation: part(9),
+            accent,
+            mora_size,
+            chain_rule: part(11),
+            chain_flag: parts.get(12).and_then(|x| x.parse().ok()).unwrap_or(-1),
+        }
+    }
+}
+
+/// Result of every stage of the text front end
+///
+/// # Fields
+/// - `normalized_text`: Text after `num2word` and normalization
+/// - `words`: Words with their part of speech and pronunciation
+/// - `accent_phrases`: Accent phrases
+/// - `kana`: Accent phrases in kana notation, accepted by `easy_synthesize_kana`
+/// - `phones`, `tones`: Phone and tone sequences
+/// - `word2ph`: Number of phones owned by each character of `bert_text`
+/// - `bert_text`: Text passed to the tokenizer
+/// - `token_ids`: BERT token ids
+#[derive(Debug, Clone, Serialize)]
+pub struct TextAnalysis {
+    pub normalized_text: String,
+    pub words: Vec<WordAnalysis>,
+    pub accent_phrases: Vec<AccentPhrase>,
+    pub kana: String,
+    pub phones: Vec<String>,
+    pub tones: Vec<i32>,
+    pub word2ph: Vec<i32>,
+    pub bert_text: String,
+    pub token_ids: Vec<i64>,
+}
+
+impl TextAnalysis {
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+/// Run the text front end and return the result of every stage
+///
+/// # Examples
+///
+/// ```rs
+/// let analysis = analyze_text("今日はいい天気ですね", &jtalk, &tokenizer)?;
+/// println!("{}", analysis.kana);
+/// ```
+pub fn analyze_text(
+    text: &str,
+    jtalk: &jtalk::JTalk,
+    tokenizer: &Tokenizer,
+) -> Result<TextAnalysis> {
+    let (normalized_text, process) = tts_util::preprocess_parse_text(text, jtalk)?;
+    let (phones, tones, word2ph) = process.g2p()?;
+    let accent_phrases = process.accent_phrases()?;
+    let bert_text = {
+        let (seq_text, _) = process.text_to_seq_kata()?;
+        seq_text.join("")
+    };
+    let (token_ids, _) = tokenizer::tokenize(&bert_text, tokenizer)?;
+    Ok(TextAnalysis {
+        normalized_text,
+        words: process.words(),
+        kana: kana::to_kana(&accent_phrases),
+        accent_phrases,
+        phones,
+        tones,
+        word2ph,
+        bert_text,
+        token_ids,
+    })
+}
@@ -0,0 +1,149 @@
+//! Inference backends BERT and VITS2 run on
+//!
+//! ONNX Runtime (`ort` feature) is the default. `tract` is a pure-Rust backend
+//! which needs no native library, e.g. for static musl builds.
+use crate::error::{Error, Result};
+use crate::model::SessionConfig;
+use ndarray::ArrayD;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[cfg(feature = "mock")]
+pub mod mock;
+#[cfg(feature = "ort")]
+pub mod onnxruntime;
+#[cfg(feature = "tract")]
+pub mod tract;
+
+#[cfg(not(any(feature = "ort", feature = "tract", feature = "mock")))]
+compile_error!("the `std` feature needs an inference backend, enable `ort` or `tract`");
+
+/// Tensor passed to and returned from an `InferenceBackend`
+///
+/// Backends convert float tensors to the precision the model expects, e.g. float16.
+#[derive(Debug, Clone)]
+pub enum TensorData {
+    Float(ArrayD<f32>),
+    Int64(ArrayD<i64>),
+}
+
+impl TensorData {
+    pub fn into_f32(self) -> Result<ArrayD<f32>> {
+        match self {
+            TensorData::Float(array) => Ok(array),
+            TensorData::Int64(_) => Err(Error::ValueError(
+                "expected a float tensor, got int64".to_string(),
+            )),
+        }
+    }
+
+    pub fn into_i64(self) -> Result<ArrayD<i64>> {
+        match self {
+            TensorData::Int64(array) => Ok(array),
+            TensorData::Float(_) => Err(Error::ValueError(
+                "expected an int64 tensor, got float".to_string(),
+            )),
+        }
+    }
+}
+
+impl From<ArrayD<f32>> for TensorData {
+    fn from(value: ArrayD<f32>) -> Self {
+        TensorData::Float(value)
+    }
+}
+
+impl From<ArrayD<i64>> for TensorData {
+    fn from(value: ArrayD<i64>) -> Self {
+        TensorData::Int64(value)
+    }
+}
+
+/// Outputs of `InferenceBackend::run` by name
+pub type Outputs = HashMap<String, TensorData>;
+
+/// Remove the output `name`, failing when the model does not have it
+pub fn take_output(outputs: &mut Outputs, name: &str) -> Result<TensorData> {
+    outputs
+        .remove(name)
+        .ok_or_else(|| Error::ValueError(format!("model has no output `{name}`")))
+}
+
+/// A loaded ONNX model
+///
+/// `bert::predict` and `model::synthesize` only talk to models through this trait.
+pub trait InferenceBackend: Send + Sync {
+    /// Names of the model inputs
+    fn inputs(&self) -> Vec<String>;
+    /// Names of the model outputs
+    fn outputs(&self) -> Vec<String>;
+    /// Custom metadata stored in the model, e.g. the style vectors of AIVMX files
+    fn metadata(&self, key: &str) -> Result<Option<String>>;
+    /// Run the model with named inputs and return every output
+    fn run(&mut self, inputs: Vec<(&str, TensorData)>) -> Result<Outputs>;
+}
+
+/// Which `InferenceBackend` models are loaded with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    #[cfg(feature = "ort")]
+    Ort,
+    #[cfg(feature = "tract")]
+    Tract,
+    /// Fake models for tests, see `mock::MockBackend`
+    #[cfg(feature = "mock")]
+    Mock,
+}
+
+impl Default for Backend {
+    #[allow(unreachable_code)]
+    fn default() -> Self {
+        #[cfg(feature = "ort")]
+        return Backend::Ort;
+        #[cfg(feature = "tract")]
+        return Backend::Tract;
+        #[cfg(feature = "mock")]
+        return Backend::Mock;
+    }
+}
+
+impl FromStr for Backend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            #[cfg(feature = "ort")]
+            "ort" | "onnxruntime" => Ok(Backend::Ort),
+            #[cfg(feature = "tract")]
+            "tract" => Ok(Backend::Tract),
+            #[cfg(feature = "mock")]
+            "mock" => Ok(Backend::Mock),
+            _ => Err(Error::ValueError(format!(
+                "unknown or disabled inference backend: {s}"
+            ))),
+        }
+    }
+}
+
+/// Load an onnx model with `config.backend`, `name` is used for files written next to it
+#[allow(unused_variables)]
+pub fn load_model<P: AsRef<[u8]>>(
+    model_file: P,
+    bert: bool,
+    config: &SessionConfig,
+    name: &str,
+) -> Result<Box<dyn InferenceBackend>> {
+    match config.backend {
+        #[cfg(feature = "ort")]
+        Backend::Ort => Ok(Box::new(onnxruntime::OrtBackend::new(
+            model_file.as_ref(),
+            bert,
+            config,
+            name,
+        )?)),
+        #[cfg(feature = "tract")]
+        Backend::Tract => Ok(Box::new(tract::TractBackend::new(model_file.as_ref())?)),
+        #[cfg(feature = "mock")]
+        Backend::Mock => Ok(Box::new(mock::MockBackend::new(bert, model_file.as_ref())?)),
+    }
+}
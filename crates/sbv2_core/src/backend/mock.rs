@@ -0,0 +1,219 @@
+//! Deterministic fake models for tests, no model file is read
+//!
+//! BERT returns `(tokens, BERT_HIDDEN_SIZE)` features derived from the token ids,
+//! and VITS2 returns a sine wave of `SAMPLES_PER_PHONE * length_scale` samples per phone.
+//! An empty model file behaves like a `--batch` export, `model_json` describes other exports.
+//! Inputs are checked against the declared ones, and the Chinese and English BERT inputs
+//! of multilingual exports have to be zero-filled.
+use super::{InferenceBackend, Outputs, TensorData};
+use crate::error::{Error, Result};
+use crate::tts_util::BERT_HIDDEN_SIZE;
+use ndarray::{Array1, Array3, ArrayD, Axis, Ix1, Ix2};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Samples VITS2 renders for each phone at `length_scale` 1.0
+pub const SAMPLES_PER_PHONE: usize = 512;
+
+const BERT_INPUTS: &[&str] = &["input_ids", "attention_mask"];
+const VITS2_INPUTS: &[&str] = &[
+    "x_tst",
+    "x_tst_lengths",
+    "sid",
+    "tones",
+    "language",
+    "bert",
+    "style_vec",
+    "sdp_ratio",
+    "length_scale",
+    "noise_scale",
+    "noise_scale_w",
+];
+
+/// Inputs, outputs and metadata of a mock model, the model file is this as JSON
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct MockModel {
+    /// Defaults to the inputs of a JP-Extra export, see `multilingual_inputs`
+    pub inputs: Option<Vec<String>>,
+    /// Defaults to `output`, and `y_lengths` for VITS2
+    pub outputs: Option<Vec<String>>,
+    /// Defaults to `batch` = `true` for BERT
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+pub struct MockBackend {
+    bert: bool,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    metadata: HashMap<String, String>,
+}
+
+impl MockBackend {
+    pub fn new(bert: bool, model_file: &[u8]) -> Result<Self> {
+        let model: MockModel = if model_file.is_empty() {
+            MockModel::default()
+        } else {
+            serde_json::from_slice(model_file)?
+        };
+        let inputs = if bert { BERT_INPUTS } else { VITS2_INPUTS };
+        let outputs: &[&str] = if bert {
+            &["output"]
+        } else {
+            &["output", "y_lengths"]
+        };
+        let metadata = if bert {
+            HashMap::from([("batch".to_string(), "true".to_string())])
+        } else {
+            HashMap::new()
+        };
+        Ok(MockBackend {
+            bert,
+            inputs: model
+                .inputs
+                .unwrap_or_else(|| inputs.iter().map(|x| x.to_string()).collect()),
+            outputs: model
+                .outputs
+                .unwrap_or_else(|| outputs.iter().map(|x| x.to_string()).collect()),
+            metadata: model.metadata.unwrap_or(metadata),
+        })
+    }
+
+    fn run_bert(&self, inputs: &mut HashMap<&str, TensorData>) -> Result<Outputs> {
+        let token_ids = take_input(inputs, "input_ids")?
+            .into_i64()?
+            .into_dimensionality::<Ix2>()?;
+        let (batch, len) = token_ids.dim();
+        let features = ArrayD::from_shape_fn(vec![batch, len, BERT_HIDDEN_SIZE], |i| {
+            ((token_ids[[i[0], i[1]]] + i[2] as i64) % 17) as f32 / 17.0
+        });
+        // Stock exports only return the first text, and batch 1 exports drop the batch axis
+        let features = if batch == 1 || !self.metadata.contains_key("batch") {
+            features.index_axis_move(Axis(0), 0)
+        } else {
+            features
+        };
+        Ok(HashMap::from([("output".to_string(), features.into())]))
+    }
+
+    fn run_vits2(&self, inputs: &mut HashMap<&str, TensorData>) -> Result<Outputs> {
+        // Only Japanese text is synthesized, so the other languages have no features
+        if inputs.contains_key("ja_bert") {
+            for name in ["bert", "en_bert"] {
+                if take_input(inputs, name)?
+                    .into_f32()?
+                    .iter()
+                    .any(|&x| x != 0.0)
+                {
+                    return Err(Error::ValueError(format!("`{name}` is not zero-filled")));
+                }
+            }
+        }
+        let lengths = take_input(inputs, "x_tst_lengths")?
+            .into_i64()?
+            .into_dimensionality::<Ix1>()?;
+        let length_scale = take_input(inputs, "length_scale")?
+            .into_f32()?
+            .first()
+            .copied()
+            .unwrap_or(1.0);
+        let samples =
+            |len: i64| (len.max(0) as f32 * SAMPLES_PER_PHONE as f32 * length_scale) as usize;
+        let y_lengths: Array1<i64> = lengths.mapv(|len| samples(len) as i64);
+        let max_len = lengths.iter().map(|&len| samples(len)).max().unwrap_or(0);
+        let mut audio = Array3::<f32>::zeros((lengths.len(), 1, max_len));
+        for (i, &len) in y_lengths.iter().enumerate() {
+            for t in 0..len as usize {
+                let phase = t as f32 * 440.0 * std::f32::consts::TAU / 44100.0;
+                audio[[i, 0, t]] = 0.1 * phase.sin();
+            }
+        }
+        let mut outputs = HashMap::from([
+            ("output".to_string(), audio.into_dyn().into()),
+            ("y_lengths".to_string(), y_lengths.into_dyn().into()),
+        ]);
+        outputs.retain(|name, _| self.outputs.contains(name));
+        Ok(outputs)
+    }
+}
+
+fn take_input(inputs: &mut HashMap<&str, TensorData>, name: &str) -> Result<TensorData> {
+    inputs
+        .remove(name)
+        .ok_or_else(|| Error::ValueError(format!("missing input `{name}`")))
+}
+
+impl InferenceBackend for MockBackend {
+    fn inputs(&self) -> Vec<String> {
+        self.inputs.clone()
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        self.outputs.clone()
+    }
+
+    fn metadata(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.metadata.get(key).cloned())
+    }
+
+    fn run(&mut self, inputs: Vec<(&str, TensorData)>) -> Result<Outputs> {
+        let mut inputs: HashMap<&str, TensorData> = inputs.into_iter().collect();
+        let mut names: Vec<&str> = inputs.keys().copied().collect();
+        let mut expected: Vec<&str> = self.inputs.iter().map(|x| x.as_str()).collect();
+        names.sort_unstable();
+        expected.sort_unstable();
+        if names != expected {
+            return Err(Error::ValueError(format!(
+                "inputs {names:?} do not match the model inputs {expected:?}"
+            )));
+        }
+        if self.bert {
+            self.run_bert(&mut inputs)
+        } else {
+            self.run_vits2(&mut inputs)
+        }
+    }
+}
+
+/// Tokenizer whose every character is `[UNK]`, for `TTSModelHolder::new`
+pub fn tokenizer_json() -> Vec<u8> {
+    br#"{
+  "version": "1.0",
+  "truncation": null,
+  "padding": null,
+  "added_tokens": [],
+  "normalizer": null,
+  "pre_tokenizer": null,
+  "post_processor": null,
+  "decoder": null,
+  "model": {
+    "type": "WordLevel",
+    "vocab": {"[PAD]": 0, "[CLS]": 1, "[SEP]": 2, "[UNK]": 3},
+    "unk_token": "[UNK]"
+  }
+}"#
+    .to_vec()
+}
+
+/// VITS2 inputs of a Style-Bert-VITS2 2.x export, with `ja_bert` and `en_bert`
+pub fn multilingual_inputs() -> Vec<String> {
+    VITS2_INPUTS
+        .iter()
+        .copied()
+        .chain(["ja_bert", "en_bert"])
+        .map(|x| x.to_string())
+        .collect()
+}
+
+/// Model file of a mock model, e.g. of a stock export without batch support
+pub fn model_json(model: &MockModel) -> Vec<u8> {
+    serde_json::to_vec(model).expect("mock models are serializable")
+}
+
+/// Style vectors of `styles` styles for `TTSModelHolder::load`
+pub fn style_vectors_json(styles: usize) -> Vec<u8> {
+    let data: Vec<Vec<f32>> = (0..styles)
+        .map(|i| (0..256).map(|j| ((i + j) % 5) as f32 / 5.0).collect())
+        .collect();
+    serde_json::to_vec(&serde_json::json!({ "shape": [styles, 256], "data": data }))
+        .expect("style vectors are serializable")
+}
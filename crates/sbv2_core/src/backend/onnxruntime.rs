@@ -0,0 +1,241 @@
+use super::{InferenceBackend, Outputs, TensorData};
+use crate::error::{Error, Result};
+use crate::model::{OptimizationLevel, SessionConfig};
+use half::f16;
+use ndarray::ArrayD;
+use ort::session::builder::{GraphOptimizationLevel, SessionBuilder};
+use ort::session::Session;
+use ort::tensor::TensorElementType;
+use ort::value::{DynValue, Tensor, ValueType};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+const EXECUTION_PROVIDERS: &[&str] = &[
+    #[cfg(feature = "tensorrt")]
+    "tensorrt",
+    #[cfg(feature = "cuda")]
+    "cuda",
+    #[cfg(feature = "cuda_tf32")]
+    "cuda_tf32",
+    #[cfg(feature = "directml")]
+    "directml",
+    #[cfg(feature = "coreml")]
+    "coreml",
+    "cpu",
+];
+
+impl From<OptimizationLevel> for GraphOptimizationLevel {
+    fn from(value: OptimizationLevel) -> Self {
+        match value {
+            OptimizationLevel::Disable => GraphOptimizationLevel::Disable,
+            OptimizationLevel::Basic => GraphOptimizationLevel::Level1,
+            OptimizationLevel::Extended => GraphOptimizationLevel::Level2,
+            OptimizationLevel::All => GraphOptimizationLevel::Level3,
+        }
+    }
+}
+
+/// ONNX Runtime session
+pub struct OrtBackend {
+    session: Session,
+    /// Inputs exported as float16, read from the model when it is loaded
+    f16_inputs: HashSet<String>,
+}
+
+impl OrtBackend {
+    pub fn new(model_file: &[u8], bert: bool, config: &SessionConfig, name: &str) -> Result<Self> {
+        Ok(Self::from_session(load_session(
+            model_file, bert, config, name,
+        )?))
+    }
+
+    pub fn from_session(session: Session) -> Self {
+        let f16_inputs = session
+            .inputs
+            .iter()
+            .filter(|input| {
+                matches!(
+                    input.input_type,
+                    ValueType::Tensor {
+                        ty: TensorElementType::Float16,
+                        ..
+                    }
+                )
+            })
+            .map(|input| input.name.clone())
+            .collect();
+        OrtBackend {
+            session,
+            f16_inputs,
+        }
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+}
+
+impl InferenceBackend for OrtBackend {
+    fn inputs(&self) -> Vec<String> {
+        self.session.inputs.iter().map(|x| x.name.clone()).collect()
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        self.session
+            .outputs
+            .iter()
+            .map(|x| x.name.clone())
+            .collect()
+    }
+
+    fn metadata(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.session.metadata()?.custom(key)?)
+    }
+
+    fn run(&mut self, inputs: Vec<(&str, TensorData)>) -> Result<Outputs> {
+        let inputs = inputs
+            .into_iter()
+            .map(|(name, data)| {
+                let value = match data {
+                    TensorData::Float(array) if self.f16_inputs.contains(name) => {
+                        Tensor::from_array(array.mapv(f16::from_f32))?.into_dyn()
+                    }
+                    TensorData::Float(array) => {
+                        Tensor::from_array(standard_layout(array))?.into_dyn()
+                    }
+                    TensorData::Int64(array) => {
+                        Tensor::from_array(standard_layout(array))?.into_dyn()
+                    }
+                };
+                Ok((name.to_string(), value))
+            })
+            .collect::<Result<Vec<(String, DynValue)>>>()?;
+        let names = self.outputs();
+        let outputs = self.session.run(inputs)?;
+        names
+            .into_iter()
+            .filter_map(|name| {
+                let value = outputs.get(name.as_str())?;
+                Some(tensor_data(value).map(|data| (name, data)))
+            })
+            .collect()
+    }
+}
+
+fn standard_layout<T: Clone>(array: ArrayD<T>) -> ArrayD<T> {
+    if array.is_standard_layout() {
+        array
+    } else {
+        array.as_standard_layout().into_owned()
+    }
+}
+
+/// Extract an output as float32 or int64
+fn tensor_data(value: &DynValue) -> Result<TensorData> {
+    if let Ok(array) = value.try_extract_array::<f32>() {
+        return Ok(TensorData::Float(array.to_owned()));
+    }
+    if let Ok(array) = value.try_extract_array::<f16>() {
+        return Ok(TensorData::Float(array.mapv(f32::from)));
+    }
+    Ok(TensorData::Int64(
+        value.try_extract_array::<i64>()?.to_owned(),
+    ))
+}
+
+#[allow(clippy::vec_init_then_push, unused_variables)]
+fn load_session(
+    model_file: &[u8],
+    bert: bool,
+    config: &SessionConfig,
+    name: &str,
+) -> Result<Session> {
+    let mut exp = Vec::new();
+    #[cfg(feature = "tensorrt")]
+    {
+        if bert {
+            exp.push(
+                ort::execution_providers::TensorRTExecutionProvider::default()
+                    .with_fp16(true)
+                    .with_profile_min_shapes("input_ids:1x1,attention_mask:1x1")
+                    .with_profile_max_shapes("input_ids:1x100,attention_mask:1x100")
+                    .with_profile_opt_shapes("input_ids:1x25,attention_mask:1x25")
+                    .build(),
+            );
+        }
+    }
+    #[cfg(feature = "cuda")]
+    {
+        #[allow(unused_mut)]
+        let mut cuda = ort::execution_providers::CUDAExecutionProvider::default();
+        #[cfg(feature = "cuda_tf32")]
+        {
+            cuda = cuda.with_tf32(true);
+        }
+        exp.push(cuda.build());
+    }
+    #[cfg(feature = "directml")]
+    {
+        exp.push(ort::execution_providers::DirectMLExecutionProvider::default().build());
+    }
+    #[cfg(feature = "coreml")]
+    {
+        exp.push(ort::execution_providers::CoreMLExecutionProvider::default().build());
+    }
+    exp.push(
+        ort::execution_providers::CPUExecutionProvider::default()
+            .with_arena_allocator(config.cpu_arena)
+            .build(),
+    );
+    if config.model_cache_dir.is_some() && config.optimized_model_dir.is_some() {
+        return Err(Error::ValueError(
+            "model_cache_dir and optimized_model_dir cannot be set together".to_string(),
+        ));
+    }
+    let builder = |level: OptimizationLevel| -> Result<SessionBuilder> {
+        Ok(Session::builder()?
+            .with_execution_providers(exp.clone())?
+            .with_optimization_level(level.into())?
+            .with_intra_threads(config.intra_threads)?
+            .with_parallel_execution(config.parallel_execution)?
+            .with_inter_threads(config.inter_threads)?
+            .with_memory_pattern(config.memory_pattern)?)
+    };
+    let Some(dir) = &config.model_cache_dir else {
+        let mut builder = builder(config.optimization_level)?;
+        if let Some(dir) = &config.optimized_model_dir {
+            std::fs::create_dir_all(dir)?;
+            builder = builder.with_optimized_model_path(dir.join(format!("{name}.onnx")))?;
+        }
+        return Ok(builder.commit_from_memory(model_file)?);
+    };
+    let path = dir.join(format!("{}.onnx", cache_key(model_file, bert, config)));
+    if !path.exists() {
+        std::fs::create_dir_all(dir)?;
+        // `All` adds layout optimizations specific to the CPU, which a saved graph must not have
+        let level = config.optimization_level.min(OptimizationLevel::Extended);
+        // Write to a temporary file first so that other processes never load a partial graph
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        builder(level)?
+            .with_optimized_model_path(&tmp)?
+            .commit_from_memory(model_file)?;
+        std::fs::rename(&tmp, &path)?;
+    }
+    Ok(builder(config.optimization_level)?.commit_from_file(&path)?)
+}
+
+/// Key of an optimized graph in `SessionConfig::model_cache_dir`
+///
+/// Optimized graphs depend on the ONNX Runtime version, the execution providers and the
+/// level they are saved at, so these are hashed together with the model.
+fn cache_key(model_file: &[u8], bert: bool, config: &SessionConfig) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(model_file);
+    hasher.update(format!(
+        "ort=1.{};bert={bert};level={:?};providers={}",
+        ort::MINOR_VERSION,
+        config.optimization_level.min(OptimizationLevel::Extended),
+        EXECUTION_PROVIDERS.join(",")
+    ));
+    format!("{:x}", hasher.finalize())
+}
@@ -0,0 +1,107 @@
+use super::{InferenceBackend, Outputs, TensorData};
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+use std::io::Cursor;
+use tract_onnx::prelude::*;
+
+/// Pure-Rust backend built on tract
+///
+/// `SessionConfig` is ignored except for the backend choice.
+pub struct TractBackend {
+    model: TypedRunnableModel<TypedModel>,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    f16_inputs: Vec<bool>,
+    metadata: HashMap<String, String>,
+}
+
+impl TractBackend {
+    pub fn new(model_file: &[u8]) -> Result<Self> {
+        let onnx = tract_onnx::onnx();
+        let proto = onnx.proto_model_for_read(&mut Cursor::new(model_file))?;
+        let metadata = proto
+            .metadata_props
+            .iter()
+            .map(|x| (x.key.clone(), x.value.clone()))
+            .collect();
+        let model = onnx
+            .model_for_proto_model(&proto)?
+            .into_optimized()?
+            .into_runnable()?;
+        let graph = model.model();
+        let inputs = graph
+            .input_outlets()?
+            .iter()
+            .map(|outlet| graph.node(outlet.node).name.clone())
+            .collect();
+        let f16_inputs = (0..graph.inputs.len())
+            .map(|i| Ok(graph.input_fact(i)?.datum_type == f16::datum_type()))
+            .collect::<Result<_>>()?;
+        let outputs = graph
+            .output_outlets()?
+            .iter()
+            .map(|&outlet| {
+                graph
+                    .outlet_label(outlet)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| graph.node(outlet.node).name.clone())
+            })
+            .collect();
+        Ok(TractBackend {
+            model,
+            inputs,
+            outputs,
+            f16_inputs,
+            metadata,
+        })
+    }
+}
+
+impl InferenceBackend for TractBackend {
+    fn inputs(&self) -> Vec<String> {
+        self.inputs.clone()
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        self.outputs.clone()
+    }
+
+    fn metadata(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.metadata.get(key).cloned())
+    }
+
+    fn run(&mut self, inputs: Vec<(&str, TensorData)>) -> Result<Outputs> {
+        let mut inputs: HashMap<&str, TensorData> = inputs.into_iter().collect();
+        // tract takes the inputs in the order of the graph
+        let values = self
+            .inputs
+            .iter()
+            .zip(&self.f16_inputs)
+            .map(|(name, &f16_input)| {
+                let tensor = match inputs.remove(name.as_str()) {
+                    Some(TensorData::Float(array)) if f16_input => {
+                        array.into_tensor().cast_to::<f16>()?.into_owned()
+                    }
+                    Some(TensorData::Float(array)) => array.into_tensor(),
+                    Some(TensorData::Int64(array)) => array.into_tensor(),
+                    None => return Err(Error::ValueError(format!("missing input `{name}`"))),
+                };
+                Ok(tensor.into_tvalue())
+            })
+            .collect::<Result<TVec<TValue>>>()?;
+        let outputs = self.model.run(values)?;
+        self.outputs
+            .iter()
+            .zip(outputs)
+            .map(|(name, value)| {
+                let tensor = value.into_tensor();
+                let data = if tensor.datum_type() == i64::datum_type() {
+                    TensorData::Int64(tensor.into_array::<i64>()?)
+                } else {
+                    TensorData::Float(tensor.cast_to::<f32>()?.into_owned().into_array::<f32>()?)
+                };
+                Ok((name.clone(), data))
+            })
+            .collect()
+    }
+}
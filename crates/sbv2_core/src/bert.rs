@@ -1,22 +1,74 @@
-use crate::error::Result;
-use ndarray::{Array2, Ix2};
-use ort::session::Session;
-use ort::value::TensorRef;
+use crate::backend::{take_output, InferenceBackend};
+use crate::error::{Error, Result};
+use crate::tokenizer::TokenBatch;
+use ndarray::{s, Array2, Axis, Ix2, Ix3};
 
 pub fn predict(
-    session: &mut Session,
+    session: &mut dyn InferenceBackend,
     token_ids: Vec<i64>,
     attention_masks: Vec<i64>,
 ) -> Result<Array2<f32>> {
-    let outputs = session.run(
-        ort::inputs! {
-            "input_ids" => TensorRef::from_array_view((vec![1, token_ids.len() as i64], token_ids.as_slice()))?,
-            "attention_mask" => TensorRef::from_array_view((vec![1, attention_masks.len() as i64], attention_masks.as_slice()))?,
+    let len = token_ids.len();
+    let mut outputs = session.run(vec![
+        (
+            "input_ids",
+            Array2::from_shape_vec((1, len), token_ids)?
+                .into_dyn()
+                .into(),
+        ),
+        (
+            "attention_mask",
+            Array2::from_shape_vec((1, len), attention_masks)?
+                .into_dyn()
+                .into(),
+        ),
+    ])?;
+    let output = take_output(&mut outputs, "output")?.into_f32()?;
+    // Batched exports keep the batch axis
+    let output = match output.ndim() {
+        3 => output.index_axis_move(Axis(0), 0),
+        _ => output,
+    };
+    Ok(output.into_dimensionality::<Ix2>()?)
+}
+
+/// Whether `session` was exported with a batch axis, see `convert_deberta.py --batch`
+///
+/// Batched exports store `batch` = `true` in their metadata.
+pub fn supports_batch(session: &dyn InferenceBackend) -> Result<bool> {
+    Ok(session.metadata("batch")?.as_deref() == Some("true"))
+}
+
+/// Run BERT once for several padded texts and return the features of each without padding
+///
+/// The model has to be exported with a batch axis, i.e. its output is `(batch, tokens, hidden)`,
+/// see `supports_batch`.
+pub fn predict_batch(
+    session: &mut dyn InferenceBackend,
+    batch: &TokenBatch,
+) -> Result<Vec<Array2<f32>>> {
+    let mut outputs = session.run(vec![
+        ("input_ids", batch.token_ids.clone().into_dyn().into()),
+        (
+            "attention_mask",
+            batch.attention_masks.clone().into_dyn().into(),
+        ),
+    ])?;
+    let output = take_output(&mut outputs, "output")?.into_f32()?;
+    let output = match output.ndim() {
+        2 if batch.lengths.len() == 1 => output.into_dimensionality::<Ix2>()?.insert_axis(Axis(0)),
+        3 => output.into_dimensionality::<Ix3>()?,
+        _ => {
+            return Err(Error::ValueError(format!(
+                "BERT output of shape {:?} has no batch axis",
+                output.shape()
+            )))
         }
-    )?;
-    let output = outputs["output"]
-        .try_extract_array::<f32>()?
-        .into_dimensionality::<Ix2>()?
-        .to_owned();
-    Ok(output)
+    };
+    Ok(batch
+        .lengths
+        .iter()
+        .enumerate()
+        .map(|(i, &len)| output.slice(s![i, ..len, ..]).to_owned())
+        .collect())
 }
@@ -0,0 +1,235 @@
+use crate::error::{Error, Result};
+use ndarray::Array2;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Write};
+
+/// Changed with the file format, the second version added the fingerprint
+const MAGIC: &[u8; 8] = b"SBV2BRT2";
+
+/// SHA-256 of the BERT model and the tokenizer, which every cached feature depends on
+pub fn fingerprint(bert_model_bytes: &[u8], tokenizer_bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for bytes in [bert_model_bytes, tokenizer_bytes] {
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(bytes);
+    }
+    hasher.finalize().into()
+}
+
+/// Hit and miss counters of a `BertCache`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BertCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub len: usize,
+    pub capacity: usize,
+}
+
+/// Bounded least-recently-used cache of BERT features
+///
+/// Keys are the texts passed to `tokenizer::tokenize`, so the same sentence is
+/// looked up once it has been normalized, whatever its original spelling was.
+/// A capacity of 0 disables the cache. Saved files carry the `fingerprint` of the
+/// cache, and only load into a cache with the same one.
+///
+/// # Examples
+///
+/// ```rs
+/// let mut cache = BertCache::new(1024).with_fingerprint(fingerprint(&bert_bytes, &tokenizer_bytes));
+/// if cache.get("コンニチワ").is_none() {
+///     cache.insert("コンニチワ".to_string(), bert::predict(&mut session, token_ids, attention_masks)?);
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BertCache {
+    capacity: usize,
+    entries: HashMap<String, Array2<f32>>,
+    order: VecDeque<String>,
+    hits: u64,
+    misses: u64,
+    fingerprint: [u8; 32],
+}
+
+impl BertCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    /// Set the fingerprint of the BERT model and the tokenizer that make the features
+    pub fn with_fingerprint(mut self, fingerprint: [u8; 32]) -> Self {
+        self.fingerprint = fingerprint;
+        self
+    }
+
+    pub fn fingerprint(&self) -> [u8; 32] {
+        self.fingerprint
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change the capacity, evicting the least recently used entries if needed
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn stats(&self) -> BertCacheStats {
+        BertCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            len: self.len(),
+            capacity: self.capacity,
+        }
+    }
+
+    /// Remove every entry and reset the counters
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    fn touch(&mut self, key: &str) {
+        if let Some(i) = self.order.iter().position(|x| x == key) {
+            if let Some(key) = self.order.remove(i) {
+                self.order.push_back(key);
+            }
+        }
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            match self.order.pop_front() {
+                Some(key) => {
+                    self.entries.remove(&key);
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Look up the features of `text`, counting a hit or a miss
+    pub fn get(&mut self, text: &str) -> Option<Array2<f32>> {
+        if self.capacity == 0 {
+            return None;
+        }
+        match self.entries.get(text).cloned() {
+            Some(features) => {
+                self.hits += 1;
+                self.touch(text);
+                Some(features)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, text: String, features: Array2<f32>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.insert(text.clone(), features).is_some() {
+            self.touch(&text);
+        } else {
+            self.order.push_back(text);
+            self.evict();
+        }
+    }
+
+    /// Write every entry, least recently used first
+    pub fn save<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&self.fingerprint)?;
+        writer.write_all(&(self.order.len() as u64).to_le_bytes())?;
+        for key in &self.order {
+            let features = &self.entries[key];
+            writer.write_all(&(key.len() as u64).to_le_bytes())?;
+            writer.write_all(key.as_bytes())?;
+            writer.write_all(&(features.nrows() as u64).to_le_bytes())?;
+            writer.write_all(&(features.ncols() as u64).to_le_bytes())?;
+            for x in features.iter() {
+                writer.write_all(&x.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read entries written by `save`, keeping the current capacity
+    ///
+    /// Nothing is read from a file with another fingerprint or a corrupt one.
+    pub fn load<R: Read>(&mut self, mut reader: R) -> Result<()> {
+        let mut file = vec![];
+        reader.read_to_end(&mut file)?;
+        let mut file = file.as_slice();
+        if take(&mut file, MAGIC.len())? != MAGIC {
+            return Err(Error::ValueError("not a BERT cache file".to_string()));
+        }
+        if take(&mut file, self.fingerprint.len())? != self.fingerprint {
+            return Err(Error::ValueError(
+                "BERT cache made with another BERT model or tokenizer".to_string(),
+            ));
+        }
+        let n = read_u64(&mut file)?;
+        let mut entries = vec![];
+        for _ in 0..n {
+            let len = read_len(&mut file)?;
+            let key = String::from_utf8(take(&mut file, len)?.to_vec())
+                .map_err(|e| Error::ValueError(format!("invalid BERT cache key: {e}")))?;
+            let rows = read_len(&mut file)?;
+            let cols = read_len(&mut file)?;
+            let len = rows
+                .checked_mul(cols)
+                .and_then(|x| x.checked_mul(4))
+                .ok_or_else(|| Error::ValueError("invalid BERT cache shape".to_string()))?;
+            let data = take(&mut file, len)?
+                .chunks_exact(4)
+                .map(|x| f32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+                .collect();
+            entries.push((key, Array2::from_shape_vec((rows, cols), data)?));
+        }
+        for (key, features) in entries {
+            self.insert(key, features);
+        }
+        Ok(())
+    }
+}
+
+/// Split the next `len` bytes off the file, failing when fewer are left
+fn take<'a>(file: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if len > file.len() {
+        return Err(Error::ValueError("truncated BERT cache file".to_string()));
+    }
+    let (head, tail) = file.split_at(len);
+    *file = tail;
+    Ok(head)
+}
+
+fn read_u64(file: &mut &[u8]) -> Result<u64> {
+    let bytes = take(file, 8)?;
+    Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Read a length, which can not be more than the bytes left in the file
+fn read_len(file: &mut &[u8]) -> Result<usize> {
+    usize::try_from(read_u64(file)?)
+        .ok()
+        .filter(|&len| len <= file.len())
+        .ok_or_else(|| Error::ValueError("truncated BERT cache file".to_string()))
+}
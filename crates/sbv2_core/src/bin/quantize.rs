@@ -0,0 +1,18 @@
+use std::env;
+use std::fs;
+
+use sbv2_core::quantize::quantize_dynamic;
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = env::args().collect();
+    let [_, input, output] = args.as_slice() else {
+        anyhow::bail!("usage: sbv2_quantize <input.onnx> <output.onnx>");
+    };
+    let (model, stats) = quantize_dynamic(&fs::read(input)?)?;
+    fs::write(output, model)?;
+    println!(
+        "quantized {} MatMul nodes ({} skipped)",
+        stats.quantized, stats.skipped
+    );
+    Ok(())
+}
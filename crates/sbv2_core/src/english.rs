@@ -0,0 +1,162 @@
+use crate::error::Result;
+use crate::romaji;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+
+static ENGLISH_DICT: Lazy<HashMap<String, String>> =
+    Lazy::new(|| serde_json::from_str(include_str!("./english_dict.json")).unwrap());
+
+static WORD_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[A-Za-zＡ-Ｚａ-ｚ]+(?:['’][A-Za-zＡ-Ｚａ-ｚ]+)*").unwrap());
+
+const LETTERS: [&str; 26] = [
+    "エー",
+    "ビー",
+    "シー",
+    "ディー",
+    "イー",
+    "エフ",
+    "ジー",
+    "エイチ",
+    "アイ",
+    "ジェー",
+    "ケー",
+    "エル",
+    "エム",
+    "エヌ",
+    "オー",
+    "ピー",
+    "キュー",
+    "アール",
+    "エス",
+    "ティー",
+    "ユー",
+    "ブイ",
+    "ダブリュー",
+    "エックス",
+    "ワイ",
+    "ゼット",
+];
+
+/// Split a CamelCase word, e.g. `HTTPServer` -> `HTTP`, `Server`
+fn split_camel_case(word: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = word.char_indices().collect();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for i in 1..chars.len() {
+        let (position, c) = chars[i];
+        let prev = chars[i - 1].1;
+        let next_is_lower = chars
+            .get(i + 1)
+            .is_some_and(|(_, n)| n.is_ascii_lowercase());
+        let boundary = c.is_ascii_uppercase()
+            && (prev.is_ascii_lowercase() || (prev.is_ascii_uppercase() && next_is_lower));
+        if boundary {
+            parts.push(&word[start..position]);
+            start = position;
+        }
+    }
+    parts.push(&word[start..]);
+    parts
+}
+
+fn to_ascii(word: &str) -> String {
+    word.chars()
+        .map(|c| match c {
+            'Ａ'..='Ｚ' | 'ａ'..='ｚ' => char::from_u32(c as u32 - 0xFEE0).unwrap(),
+            '’' => '\'',
+            c => c,
+        })
+        .collect()
+}
+
+/// Spell out a word letter by letter, e.g. `API` -> `エーピーアイ`
+pub fn spell(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .map(|c| LETTERS[(c.to_ascii_lowercase() as u8 - b'a') as usize])
+        .collect()
+}
+
+/// Converts English words in Japanese text to katakana
+///
+/// Words are looked up in the following order:
+/// 1. User overrides (exact, then lowercase)
+/// 2. Built-in dictionary entries written in capitals, e.g. `NASA` -> `ナサ`
+/// 3. Other words in capitals are spelled out, e.g. `API` -> `エーピーアイ`
+/// 4. Built-in dictionary (lowercase), then each part of a CamelCase word
+/// 5. Romaji, for words that parse completely as such, e.g. `arigatou` -> `アリガトウ`
+/// 6. Spelling letter by letter
+///
+/// English words that are valid romaji too, like `note`, are read as English when they are in
+/// the dictionary. Add overrides for others, e.g. `{"tame": "テイム"}`.
+///
+/// # Examples
+///
+/// ```rs
+/// let mut converter = EnglishConverter::new();
+/// converter.insert("BERT", "バート");
+/// assert_eq!(converter.convert("GitHubのAPIとBERT"), "ギットハブのエーピーアイとバート");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EnglishConverter {
+    overrides: HashMap<String, String>,
+}
+
+impl EnglishConverter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a user override, which takes priority over the built-in rules
+    pub fn insert(&mut self, word: &str, kana: &str) {
+        self.overrides.insert(to_ascii(word), kana.to_string());
+    }
+
+    /// Load user overrides from a JSON object, e.g. `{"BERT": "バート"}`
+    pub fn load_overrides<P: AsRef<[u8]>>(&mut self, json: P) -> Result<()> {
+        let overrides: HashMap<String, String> = serde_json::from_slice(json.as_ref())?;
+        for (word, kana) in overrides {
+            self.insert(&word, &kana);
+        }
+        Ok(())
+    }
+
+    /// Convert every run of Latin letters in the text to katakana
+    pub fn convert(&self, text: &str) -> String {
+        WORD_PATTERN
+            .replace_all(text, |caps: &Captures| self.word_to_kana(&caps[0]))
+            .to_string()
+    }
+
+    /// Convert a single word to katakana
+    pub fn word_to_kana(&self, word: &str) -> String {
+        let word = to_ascii(word);
+        let lower = word.to_ascii_lowercase();
+        if let Some(kana) = self
+            .overrides
+            .get(&word)
+            .or_else(|| self.overrides.get(&lower))
+        {
+            return kana.clone();
+        }
+        if let Some(kana) = ENGLISH_DICT.get(&word) {
+            return kana.clone();
+        }
+        if word.chars().all(|c| !c.is_ascii_lowercase()) {
+            return spell(&word);
+        }
+        if let Some(kana) = ENGLISH_DICT.get(&lower) {
+            return kana.clone();
+        }
+        let parts = split_camel_case(&word);
+        if parts.len() > 1 {
+            return parts.iter().map(|part| self.word_to_kana(part)).collect();
+        }
+        if let Some(kana) = romaji::to_katakana(&word) {
+            return kana;
+        }
+        spell(&word)
+    }
+}
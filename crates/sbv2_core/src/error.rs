@@ -8,9 +8,12 @@ pub enum Error {
     JPreprocessError(#[from] jpreprocess::error::JPreprocessError),
     #[error("Lindera error: {0}")]
     LinderaError(String),
-    #[cfg(feature = "std")]
+    #[cfg(feature = "ort")]
     #[error("ONNX error: {0}")]
     OrtError(#[from] ort::Error),
+    #[cfg(feature = "tract")]
+    #[error("tract error: {0}")]
+    TractError(#[from] tract_onnx::prelude::TractError),
     #[error("NDArray error: {0}")]
     NdArrayError(#[from] ndarray::ShapeError),
     #[error("Value error: {0}")]
@@ -26,10 +29,64 @@ pub enum Error {
     #[cfg(feature = "base64")]
     #[error("base64 error")]
     Base64Error(#[from] base64::DecodeError),
+    #[cfg(feature = "quantize")]
+    #[error("ONNX decode error: {0}")]
+    ProstDecodeError(#[from] prost::DecodeError),
     #[error("other")]
     OtherError(String),
     #[error("Style error: {0}")]
     StyleError(String),
+    #[error("Speaker error: {0}")]
+    SpeakerError(String),
+    #[error("Kana parse error: {0}")]
+    KanaParseError(String),
+    #[error("Regex error: {0}")]
+    RegexError(#[from] regex::Error),
+    #[error("Preprocess error: {0}")]
+    PreprocessError(String),
+    // Front end errors locate `word` by its character `offset` in the normalized text, i.e.
+    // after preprocessing and `num2word`, see `TextAnalysis::normalized_text`
+    #[error("Empty reading for `{word}` (at {offset})")]
+    EmptyReadingError { word: String, offset: usize },
+    #[error("Unknown mora `{mora}` in `{word}` (at {offset})")]
+    UnknownMoraError {
+        word: String,
+        offset: usize,
+        mora: String,
+    },
+    #[error("Mismatched phoneme `{phone}` in `{word}` (at {offset})")]
+    PhonemeMismatchError {
+        word: String,
+        offset: usize,
+        phone: String,
+    },
+    // Raw phone input has no words, `word` is the phone and `offset` its index
+    #[error("Unknown phone `{phone}` in `{word}` (at {offset})")]
+    UnknownPhoneError {
+        word: String,
+        offset: usize,
+        phone: String,
+    },
+    #[error(
+        "Phones and tones must be non-empty and of the same length: {phones} phones, {tones} tones"
+    )]
+    PhoneLengthError { phones: usize, tones: usize },
+    #[error("Tone must be 0 or 1: {tone} (at {offset})")]
+    InvalidToneError { tone: i32, offset: usize },
+    #[error("Invalid label: {0}")]
+    LabelError(String),
+    #[error("word2ph has {word2ph} entries but BERT returned {bert} rows for `{text}`")]
+    Word2PhMismatchError {
+        text: String,
+        word2ph: usize,
+        bert: usize,
+    },
+    #[error("Character {offset} of `{text}` was encoded into {tokens} tokens instead of one")]
+    TokenAlignmentError {
+        text: String,
+        offset: usize,
+        tokens: usize,
+    },
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
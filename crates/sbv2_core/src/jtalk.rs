@@ -1,11 +1,15 @@
+use crate::analysis::WordAnalysis;
 use crate::error::{Error, Result};
+use crate::kana::{AccentPhrase, KanaMora};
 use crate::mora::{CONSONANTS, MORA_KATA_TO_MORA_PHONEMES, MORA_PHONEMES_TO_MORA_KATA, VOWELS};
 use crate::norm::{replace_punctuation, PUNCTUATIONS};
+use crate::preprocess::PreprocessPipeline;
 use jpreprocess::{kind, DefaultTokenizer, JPreprocess, SystemDictionaryConfig, UserDictionary};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use std::cmp::Reverse;
 use std::collections::HashSet;
+use std::str::FromStr;
 use std::sync::Arc;
 
 type JPreprocessType = JPreprocess<DefaultTokenizer>;
@@ -42,14 +46,60 @@ macro_rules! hash_set {
     }};
 }
 
+/// What to do with a word the front end cannot read
+///
+/// - `Skip`: Drop the word
+/// - `Kana`: Replace the word with its surface spelled in katakana
+/// - `Fail`: Return the error
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FallbackPolicy {
+    Skip,
+    Kana,
+    #[default]
+    Fail,
+}
+
+impl FromStr for FallbackPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "skip" => Ok(Self::Skip),
+            "kana" => Ok(Self::Kana),
+            "fail" => Ok(Self::Fail),
+            _ => Err(Error::ValueError(format!("unknown fallback policy: {s}"))),
+        }
+    }
+}
+
+/// Spell a word in katakana, keeping only kana and Latin letters
+fn spell_as_kana(word: &str) -> String {
+    word.chars()
+        .map(|c| match c {
+            'ぁ'..='ゖ' => char::from_u32(c as u32 + 0x60).unwrap().to_string(),
+            'ァ'..='ヺ' | 'ー' => c.to_string(),
+            c if c.is_ascii_alphabetic() => crate::english::spell(&c.to_string()),
+            _ => String::new(),
+        })
+        .collect()
+}
+
 pub struct JTalk {
     pub jpreprocess: Arc<JPreprocessType>,
+    /// Rewrites text before `num2word`
+    pub preprocessors: PreprocessPipeline,
+    /// What to do with words that have no usable reading
+    pub fallback: FallbackPolicy,
 }
 
 impl JTalk {
     pub fn new() -> Result<Self> {
         let jpreprocess = Arc::new(initialize_jtalk()?);
-        Ok(Self { jpreprocess })
+        Ok(Self {
+            jpreprocess,
+            preprocessors: PreprocessPipeline::default(),
+            fallback: FallbackPolicy::default(),
+        })
     }
 
     pub fn num2word(&self, text: &str) -> Result<String> {
@@ -63,10 +113,27 @@ impl JTalk {
         Ok(texts.join(""))
     }
 
-    pub fn process_text(&self, text: &str) -> Result<JTalkProcess> {
+    fn run_frontend(&self, text: &str) -> Result<JTalkProcess> {
         let parsed = self.jpreprocess.run_frontend(text)?;
-        let jtalk_process = JTalkProcess::new(Arc::clone(&self.jpreprocess), parsed);
-        Ok(jtalk_process)
+        Ok(JTalkProcess::new(Arc::clone(&self.jpreprocess), parsed))
+    }
+
+    /// Run the front end, applying `fallback` to words without a usable reading
+    ///
+    /// Errors locate words by their character offset in `text`, which is usually the
+    /// normalized text from `preprocess_parse_text` rather than the user's input.
+    pub fn process_text(&self, text: &str) -> Result<JTalkProcess> {
+        let jtalk_process = self.run_frontend(text)?;
+        match jtalk_process.validate() {
+            Ok(()) => Ok(jtalk_process),
+            Err(e) if self.fallback == FallbackPolicy::Fail => Err(e),
+            Err(_) => {
+                let text = jtalk_process.rewrite_unreadable(self.fallback);
+                let jtalk_process = self.run_frontend(&text)?;
+                jtalk_process.validate()?;
+                Ok(jtalk_process)
+            }
+        }
     }
 }
 
@@ -78,32 +145,44 @@ static MORA_PATTERN: Lazy<Vec<String>> = Lazy::new(|| {
 });
 static LONG_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\w)(ー*)").unwrap());
 
-fn phone_tone_to_kana(phones: Vec<String>, tones: Vec<i32>) -> Vec<(String, i32)> {
+fn phone_tone_to_kana(phones: Vec<String>, tones: Vec<i32>) -> Result<Vec<(String, i32)>> {
     let phones = &phones[1..];
     let tones = &tones[1..];
     let mut results = Vec::new();
     let mut current_mora = String::new();
-    for ((phone, _next_phone), (&tone, &next_tone)) in phones
-        .iter()
-        .zip(phones.iter().skip(1))
-        .zip(tones.iter().zip(tones.iter().skip(1)))
-    {
-        if PUNCTUATIONS.contains(&phone.clone().as_str()) {
+    for (i, (phone, &tone)) in phones.iter().zip(tones).enumerate() {
+        // The last phone is the closing `_`
+        if i + 1 == phones.len() {
+            break;
+        }
+        if PUNCTUATIONS.contains(&phone.as_str()) {
             results.push((phone.to_string(), tone));
             continue;
         }
-        if CONSONANTS.contains(&phone.clone()) {
-            assert_eq!(current_mora, "");
-            assert_eq!(tone, next_tone);
+        // `word` and `offset` are filled in by `g2kana_tone`
+        if CONSONANTS.contains(phone) {
+            if !current_mora.is_empty() {
+                return Err(Error::UnknownPhoneError {
+                    word: String::new(),
+                    offset: i + 1,
+                    phone: current_mora,
+                });
+            }
             current_mora = phone.to_string()
         } else {
             current_mora += phone;
-            let kana = MORA_PHONEMES_TO_MORA_KATA.get(&current_mora).unwrap();
+            let kana = MORA_PHONEMES_TO_MORA_KATA
+                .get(&current_mora)
+                .ok_or_else(|| Error::UnknownPhoneError {
+                    word: String::new(),
+                    offset: i + 1,
+                    phone: current_mora.clone(),
+                })?;
             results.push((kana.to_string(), tone));
             current_mora = String::new();
         }
     }
-    results
+    Ok(results)
 }
 
 pub struct JTalkProcess {
@@ -119,13 +198,97 @@ impl JTalkProcess {
         }
     }
 
+    /// Surface, pronunciation and character offset of each word
+    fn word_entries(&self) -> Vec<(String, String, usize)> {
+        let mut offset = 0;
+        self.parsed
+            .iter()
+            .map(|parts| {
+                let (surface, pron) = self.parse_to_string_and_pron(parts);
+                let length = surface.chars().count();
+                let entry = (surface, pron, offset);
+                offset += length;
+                entry
+            })
+            .collect()
+    }
+
+    /// `after_mora` is whether the previous word ends in a mora a leading `ー` can lengthen
+    fn check_word(word: &str, pron: &str, offset: usize, after_mora: bool) -> Result<()> {
+        let yomi = pron.replace('’', "");
+        if yomi.is_empty() {
+            return Err(Error::EmptyReadingError {
+                word: word.to_string(),
+                offset,
+            });
+        }
+        if yomi == "、" || yomi == "？" {
+            return Ok(());
+        }
+        if !after_mora && yomi.starts_with('ー') {
+            return Err(Error::UnknownMoraError {
+                word: word.to_string(),
+                offset,
+                mora: "ー".to_string(),
+            });
+        }
+        JTalkProcess::kata_to_phoneme_list(yomi).map_err(|mora| Error::UnknownMoraError {
+            word: word.to_string(),
+            offset,
+            mora,
+        })?;
+        Ok(())
+    }
+
+    /// Every word with the result of `check_word`
+    fn checked_words(&self) -> Vec<(String, Result<()>)> {
+        let mut after_mora = false;
+        self.word_entries()
+            .into_iter()
+            .map(|(word, pron, offset)| {
+                let result = JTalkProcess::check_word(&word, &pron, offset, after_mora);
+                after_mora = result.is_ok() && pron != "、" && pron != "？";
+                (word, result)
+            })
+            .collect()
+    }
+
+    /// Check that every word has a reading made of known moras
+    pub fn validate(&self) -> Result<()> {
+        self.checked_words()
+            .into_iter()
+            .try_for_each(|(_, result)| result)
+    }
+
+    /// Rebuild the text, replacing unreadable words according to `policy`
+    fn rewrite_unreadable(&self, policy: FallbackPolicy) -> String {
+        self.checked_words()
+            .into_iter()
+            .map(|(word, result)| {
+                if result.is_ok() {
+                    word
+                } else if policy == FallbackPolicy::Kana {
+                    spell_as_kana(&word)
+                } else {
+                    String::new()
+                }
+            })
+            .collect()
+    }
+
     fn fix_phone_tone(&self, phone_tone_list: Vec<(String, i32)>) -> Result<Vec<(String, i32)>> {
         let tone_values: HashSet<i32> = phone_tone_list
             .iter()
             .map(|(_letter, tone)| *tone)
             .collect();
-        if tone_values.len() == 1 {
-            assert!(tone_values == hash_set![0], "{:?}", tone_values);
+        if tone_values.is_empty() {
+            Ok(phone_tone_list)
+        } else if tone_values.len() == 1 {
+            if tone_values != hash_set![0] {
+                return Err(Error::LabelError(format!(
+                    "Invalid tone values {tone_values:?}"
+                )));
+            }
             Ok(phone_tone_list)
         } else if tone_values.len() == 2 {
             if tone_values == hash_set![0, 1] {
@@ -149,11 +312,21 @@ impl JTalkProcess {
     pub fn g2p(&self) -> Result<(Vec<String>, Vec<i32>, Vec<i32>)> {
         let phone_tone_list_wo_punct = self.g2phone_tone_wo_punct()?;
         let (seq_text, seq_kata) = self.text_to_seq_kata()?;
+        let entries = self.word_entries();
         let sep_phonemes = JTalkProcess::handle_long(
             seq_kata
                 .iter()
-                .map(|x| JTalkProcess::kata_to_phoneme_list(x.clone()).unwrap())
-                .collect(),
+                .zip(&entries)
+                .map(|(kata, (word, _, offset))| {
+                    JTalkProcess::kata_to_phoneme_list(kata.clone()).map_err(|mora| {
+                        Error::UnknownMoraError {
+                            word: word.clone(),
+                            offset: *offset,
+                            mora,
+                        }
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
         );
         let phone_w_punct: Vec<String> = sep_phonemes
             .iter()
@@ -162,7 +335,25 @@ impl JTalkProcess {
             .collect();
 
         let mut phone_tone_list =
-            JTalkProcess::align_tones(phone_w_punct, phone_tone_list_wo_punct)?;
+            JTalkProcess::align_tones(phone_w_punct, phone_tone_list_wo_punct).map_err(
+                |(index, phone)| {
+                    let mut end = 0;
+                    let (word, _, offset) = sep_phonemes
+                        .iter()
+                        .zip(&entries)
+                        .find(|(phonemes, _)| {
+                            end += phonemes.len();
+                            index < end
+                        })
+                        .map(|(_, entry)| entry.clone())
+                        .unwrap_or_default();
+                    Error::PhonemeMismatchError {
+                        word,
+                        offset,
+                        phone,
+                    }
+                },
+            )?;
 
         let mut sep_tokenized: Vec<Vec<String>> = Vec::new();
         for seq_text_item in &seq_text {
@@ -196,11 +387,110 @@ impl JTalkProcess {
     }
 
     pub fn g2kana_tone(&self) -> Result<Vec<(String, i32)>> {
-        let (phones, tones, _) = self.g2p()?;
-        Ok(phone_tone_to_kana(phones, tones))
+        let (phones, tones, word2ph) = self.g2p()?;
+        phone_tone_to_kana(phones, tones).map_err(|e| match e {
+            Error::UnknownPhoneError { offset, phone, .. } => {
+                let (word, offset) = self.word_of_phone(&word2ph, offset);
+                Error::UnknownPhoneError {
+                    word,
+                    offset,
+                    phone,
+                }
+            }
+            e => e,
+        })
+    }
+
+    /// Word and character offset the `index`-th phone of `g2p` belongs to
+    fn word_of_phone(&self, word2ph: &[i32], index: usize) -> (String, usize) {
+        let mut end = 0;
+        // `word2ph` has an entry for the leading `_` and each token of `text_to_seq_kata`
+        let token = word2ph
+            .iter()
+            .position(|&n| {
+                end += n as usize;
+                index < end
+            })
+            .unwrap_or(word2ph.len());
+        let Ok((seq_text, _)) = self.text_to_seq_kata() else {
+            return Default::default();
+        };
+        let mut end = 0;
+        let word = seq_text.iter().position(|text| {
+            end += if PUNCTUATIONS.contains(&text.as_str()) {
+                1
+            } else {
+                text.chars().count()
+            };
+            token <= end
+        });
+        word.and_then(|i| self.word_entries().into_iter().nth(i))
+            .map(|(word, _, offset)| (word, offset))
+            .unwrap_or_default()
+    }
+
+    /// Words with their part of speech and pronunciation
+    pub fn words(&self) -> Vec<WordAnalysis> {
+        self.parsed
+            .iter()
+            .map(|x| WordAnalysis::from_njd(x))
+            .collect()
+    }
+
+    /// Accent phrases, derived from the prosody symbols
+    pub fn accent_phrases(&self) -> Result<Vec<AccentPhrase>> {
+        let prosodies = self.g2p_prosody()?;
+
+        let mut phrases: Vec<AccentPhrase> = Vec::new();
+        let mut moras: Vec<KanaMora> = Vec::new();
+        let mut accent = None;
+        let mut consonant: Option<String> = None;
+        for letter in prosodies {
+            match letter.as_str() {
+                "^" | "[" => {}
+                "]" => accent = Some(moras.len()),
+                "#" | "_" | "$" | "?" => {
+                    if moras.is_empty() {
+                        if let (Some(phrase), "_") = (phrases.last_mut(), letter.as_str()) {
+                            phrase.pause = true;
+                        }
+                    } else {
+                        let n_moras = moras.len();
+                        phrases.push(AccentPhrase {
+                            moras: std::mem::take(&mut moras),
+                            accent: accent.unwrap_or(n_moras),
+                            is_interrogative: letter == "?",
+                            pause: letter == "_",
+                        });
+                    }
+                    accent = None;
+                }
+                _ => {
+                    let phone = if letter == "cl" {
+                        "q".to_string()
+                    } else {
+                        letter
+                    };
+                    if CONSONANTS.contains(&phone) {
+                        consonant = Some(phone);
+                    } else {
+                        let key = format!("{}{}", consonant.clone().unwrap_or_default(), phone);
+                        moras.push(KanaMora {
+                            kana: MORA_PHONEMES_TO_MORA_KATA.get(&key).cloned().unwrap_or(key),
+                            consonant: consonant.take(),
+                            vowel: phone,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(phrases)
     }
 
-    fn distribute_phone(n_phone: i32, n_word: i32) -> Vec<i32> {
+    pub(crate) fn distribute_phone(n_phone: i32, n_word: i32) -> Vec<i32> {
+        if n_word <= 0 {
+            return vec![];
+        }
         let mut phones_per_word = vec![0; n_word as usize];
         for _ in 0..n_phone {
             let min_task = phones_per_word.iter().min().unwrap();
@@ -213,13 +503,14 @@ impl JTalkProcess {
         phones_per_word
     }
 
+    /// Returns the index and the phone on mismatch
     fn align_tones(
         phone_with_punct: Vec<String>,
         phone_tone_list: Vec<(String, i32)>,
-    ) -> Result<Vec<(String, i32)>> {
+    ) -> std::result::Result<Vec<(String, i32)>, (usize, String)> {
         let mut result: Vec<(String, i32)> = Vec::new();
         let mut tone_index = 0;
-        for phone in phone_with_punct.clone() {
+        for (i, phone) in phone_with_punct.into_iter().enumerate() {
             if tone_index >= phone_tone_list.len() {
                 result.push((phone, 0));
             } else if phone == phone_tone_list[tone_index].0 {
@@ -228,12 +519,7 @@ impl JTalkProcess {
             } else if PUNCTUATIONS.contains(&phone.as_str()) {
                 result.push((phone, 0));
             } else {
-                println!("phones {:?}", phone_with_punct);
-                println!("phone_tone_list: {:?}", phone_tone_list);
-                println!("result: {:?}", result);
-                println!("tone_index: {:?}", tone_index);
-                println!("phone: {:?}", phone);
-                return Err(Error::ValueError(format!("Mismatched phoneme: {}", phone)));
+                return Err((i, phone));
             }
         }
 
@@ -245,23 +531,18 @@ impl JTalkProcess {
             if sep_phonemes[i].is_empty() {
                 continue;
             }
-            if sep_phonemes[i][0] == "ー" {
-                if i != 0 {
-                    let prev_phoneme = sep_phonemes[i - 1].last().unwrap();
+            if sep_phonemes[i][0] == "ー" && i != 0 {
+                if let Some(prev_phoneme) = sep_phonemes[i - 1].last().cloned() {
                     if VOWELS.contains(&prev_phoneme.as_str()) {
-                        sep_phonemes[i][0] = prev_phoneme.clone();
-                    } else {
-                        sep_phonemes[i][0] = "ー".to_string();
+                        sep_phonemes[i][0] = prev_phoneme;
                     }
-                } else {
-                    sep_phonemes[i][0] = "ー".to_string();
                 }
             }
-            if sep_phonemes[i].contains(&"ー".to_string()) {
-                for e in 0..sep_phonemes[i].len() {
-                    if sep_phonemes[i][e] == "ー" {
-                        sep_phonemes[i][e] =
-                            sep_phonemes[i][e - 1].chars().last().unwrap().to_string();
+            // A leading `ー` without a preceding vowel is left as is and reported by `align_tones`
+            for e in 1..sep_phonemes[i].len() {
+                if sep_phonemes[i][e] == "ー" {
+                    if let Some(c) = sep_phonemes[i][e - 1].chars().last() {
+                        sep_phonemes[i][e] = c.to_string();
                     }
                 }
             }
@@ -269,7 +550,8 @@ impl JTalkProcess {
         sep_phonemes
     }
 
-    fn kata_to_phoneme_list(mut text: String) -> Result<Vec<String>> {
+    /// Returns the part that is not a known mora on error
+    fn kata_to_phoneme_list(mut text: String) -> std::result::Result<Vec<String>, String> {
         let chars: HashSet<String> = text.chars().map(|x| x.to_string()).collect();
         if chars.is_subset(&HashSet::from_iter(
             PUNCTUATIONS.iter().map(|x| x.to_string()),
@@ -277,22 +559,16 @@ impl JTalkProcess {
             return Ok(text.chars().map(|x| x.to_string()).collect());
         }
         if !KATAKANA_PATTERN.is_match(&text) {
-            return Err(Error::ValueError(format!(
-                "Input must be katakana only: {}",
-                text
-            )));
+            return Err(text);
         }
 
         for mora in MORA_PATTERN.iter() {
-            let mora = mora.to_string();
-            let (consonant, vowel) = MORA_KATA_TO_MORA_PHONEMES.get(&mora).unwrap();
-            if consonant.is_none() {
-                text = text.replace(&mora, &format!(" {}", vowel));
-            } else {
-                text = text.replace(
-                    &mora,
-                    &format!(" {} {}", consonant.as_ref().unwrap(), vowel),
-                );
+            if let Some((consonant, vowel)) = MORA_KATA_TO_MORA_PHONEMES.get(mora) {
+                let phonemes = match consonant {
+                    Some(consonant) => format!(" {} {}", consonant, vowel),
+                    None => format!(" {}", vowel),
+                };
+                text = text.replace(mora, &phonemes);
             }
         }
 
@@ -308,7 +584,16 @@ impl JTalkProcess {
             .replace_all(&text, long_replacement)
             .to_string();
 
-        let data = text.trim().split(' ').map(|x| x.to_string()).collect();
+        let data: Vec<String> = text.trim().split(' ').map(|x| x.to_string()).collect();
+        if let Some(unknown) = data.iter().find(|x| {
+            !(VOWELS.contains(&x.as_str())
+                || CONSONANTS.contains(*x)
+                || PUNCTUATIONS.contains(&x.as_str())
+                || *x == "q"
+                || *x == "ー")
+        }) {
+            return Err(unknown.clone());
+        }
 
         Ok(data)
     }
@@ -317,11 +602,15 @@ impl JTalkProcess {
         let mut seq_kata = vec![];
         let mut seq_text = vec![];
 
-        for parts in &self.parsed {
-            let (string, pron) = self.parse_to_string_and_pron(parts.clone());
+        for (string, pron, offset) in self.word_entries() {
             let mut yomi = pron.replace('’', "");
+            if yomi.is_empty() {
+                return Err(Error::EmptyReadingError {
+                    word: string,
+                    offset,
+                });
+            }
             let word = replace_punctuation(string);
-            assert!(!yomi.is_empty(), "Empty yomi: {}", word);
             if yomi == "、" {
                 if !word
                     .chars()
@@ -332,7 +621,6 @@ impl JTalkProcess {
                     yomi = word.clone();
                 }
             } else if yomi == "？" {
-                assert!(word == "?", "yomi `？` comes from: {}", word);
                 yomi = "?".to_string();
             }
             seq_text.push(word);
@@ -341,9 +629,10 @@ impl JTalkProcess {
         Ok((seq_text, seq_kata))
     }
 
-    fn parse_to_string_and_pron(&self, parts: String) -> (String, String) {
-        let part_lists: Vec<String> = parts.split(',').map(|x| x.to_string()).collect();
-        (part_lists[0].clone(), part_lists[9].clone())
+    fn parse_to_string_and_pron(&self, parts: &str) -> (String, String) {
+        let part_lists: Vec<&str> = parts.split(',').collect();
+        let part = |i: usize| part_lists.get(i).copied().unwrap_or_default().to_string();
+        (part(0), part(9))
     }
 
     fn g2phone_tone_wo_punct(&self) -> Result<Vec<(String, i32)>> {
@@ -355,11 +644,13 @@ impl JTalkProcess {
 
         for (i, letter) in prosodies.iter().enumerate() {
             if letter == "^" {
-                assert!(i == 0);
+                if i != 0 {
+                    return Err(Error::LabelError(format!("`^` at {i}")));
+                }
             } else if ["$", "?", "_", "#"].contains(&letter.as_str()) {
                 results.extend(self.fix_phone_tone(current_phrase.clone())?);
-                if ["$", "?"].contains(&letter.as_str()) {
-                    assert!(i == prosodies.len() - 1);
+                if ["$", "?"].contains(&letter.as_str()) && i != prosodies.len() - 1 {
+                    return Err(Error::LabelError(format!("`{letter}` at {i}")));
                 }
                 current_phrase = Vec::new();
                 current_tone = 0;
@@ -385,22 +676,30 @@ impl JTalkProcess {
 
         let mut phones: Vec<String> = Vec::new();
         for (i, label) in labels.iter().enumerate() {
-            let mut p3 = label.phoneme.c.clone().unwrap();
+            let mut p3 = label
+                .phoneme
+                .c
+                .clone()
+                .ok_or_else(|| Error::LabelError(format!("missing phoneme at {i}")))?;
             if "AIUEO".contains(&p3) {
                 // 文字をlowerする
                 p3 = p3.to_lowercase();
             }
             if p3 == "sil" {
-                assert!(i == 0 || i == labels.len() - 1);
                 if i == 0 {
                     phones.push("^".to_string());
                 } else if i == labels.len() - 1 {
-                    let e3 = label.accent_phrase_prev.clone().unwrap().is_interrogative;
+                    let e3 = label
+                        .accent_phrase_prev
+                        .as_ref()
+                        .is_some_and(|x| x.is_interrogative);
                     if e3 {
-                        phones.push("$".to_string());
-                    } else {
                         phones.push("?".to_string());
+                    } else {
+                        phones.push("$".to_string());
                     }
+                } else {
+                    return Err(Error::LabelError(format!("`sil` at {i}")));
                 }
                 continue;
             } else if p3 == "pau" {
@@ -432,7 +731,7 @@ impl JTalkProcess {
                 -50
             };
 
-            let a2_next = if let Some(mora) = &labels[i + 1].mora {
+            let a2_next = if let Some(mora) = labels.get(i + 1).and_then(|x| x.mora.as_ref()) {
                 mora.position_forward as i32
             } else {
                 -50
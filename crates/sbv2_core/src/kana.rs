@@ -0,0 +1,226 @@
+use crate::error::{Error, Result};
+use crate::mora::{MORA_KATA_TO_MORA_PHONEMES, VOWELS};
+use crate::norm::SYMBOLS;
+use serde::Serialize;
+
+const ACCENT_SYMBOL: char = '\'';
+const UNVOICE_SYMBOL: char = '_';
+const PHRASE_DELIMITER: char = '/';
+const PAUSE_DELIMITER: char = '、';
+const WIDE_INTERROGATION_MARK: char = '？';
+const INTERROGATION_MARK: char = '?';
+const LONG_VOWEL: char = 'ー';
+
+/// A mora in kana notation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KanaMora {
+    pub kana: String,
+    pub consonant: Option<String>,
+    pub vowel: String,
+}
+
+/// An accent phrase in kana notation
+///
+/// # Fields
+/// - `moras`: Moras of the phrase
+/// - `accent`: 1-based position of the accent nucleus
+/// - `is_interrogative`: Whether the phrase ends with `？`
+/// - `pause`: Whether the phrase is followed by a pause (`、`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AccentPhrase {
+    pub moras: Vec<KanaMora>,
+    pub accent: usize,
+    pub is_interrogative: bool,
+    pub pause: bool,
+}
+
+impl AccentPhrase {
+    /// Binary tone of each mora, Style-Bert-VITS2 style
+    pub fn tones(&self) -> Vec<i32> {
+        (1..=self.moras.len())
+            .map(|i| {
+                if self.accent == 1 {
+                    (i == 1) as i32
+                } else {
+                    (i != 1 && i <= self.accent) as i32
+                }
+            })
+            .collect()
+    }
+}
+
+fn error(position: usize, message: &str) -> Error {
+    Error::KanaParseError(format!("{message} (at {position})"))
+}
+
+fn parse_phrase(chars: &[(usize, char)], offset: usize) -> Result<AccentPhrase> {
+    let mut moras: Vec<KanaMora> = Vec::new();
+    let mut accent = None;
+    let mut is_interrogative = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let (position, c) = chars[i];
+        if is_interrogative {
+            return Err(error(
+                position,
+                "interrogation mark must end an accent phrase",
+            ));
+        }
+        match c {
+            ACCENT_SYMBOL => {
+                if moras.is_empty() {
+                    return Err(error(position, "accent cannot be set at the beginning"));
+                }
+                if accent.is_some() {
+                    return Err(error(position, "second accent cannot be set"));
+                }
+                accent = Some(moras.len());
+                i += 1;
+            }
+            WIDE_INTERROGATION_MARK | INTERROGATION_MARK => {
+                is_interrogative = true;
+                i += 1;
+            }
+            UNVOICE_SYMBOL => {
+                // Style-Bert-VITS2 has no devoiced phonemes, so the mark is accepted and ignored
+                i += 1;
+            }
+            LONG_VOWEL => {
+                let vowel = match moras.last() {
+                    Some(mora) if VOWELS.contains(&mora.vowel.as_str()) => mora.vowel.clone(),
+                    _ => return Err(error(position, "long vowel must follow a vowel")),
+                };
+                moras.push(KanaMora {
+                    kana: LONG_VOWEL.to_string(),
+                    consonant: None,
+                    vowel,
+                });
+                i += 1;
+            }
+            _ => {
+                let candidates = [2, 1].into_iter().filter(|n| i + n <= chars.len());
+                let mora = candidates
+                    .map(|n| chars[i..i + n].iter().map(|(_, c)| c).collect::<String>())
+                    .find_map(|kana| {
+                        MORA_KATA_TO_MORA_PHONEMES
+                            .get(&kana)
+                            .map(|(consonant, vowel)| KanaMora {
+                                kana,
+                                consonant: consonant.clone(),
+                                vowel: vowel.clone(),
+                            })
+                    })
+                    .ok_or_else(|| error(position, &format!("unknown kana `{c}`")))?;
+                if let Some(consonant) = &mora.consonant {
+                    if !SYMBOLS.contains(&consonant.as_str()) {
+                        return Err(error(
+                            position,
+                            &format!("unsupported mora `{}`", mora.kana),
+                        ));
+                    }
+                }
+                i += mora.kana.chars().count();
+                moras.push(mora);
+            }
+        }
+    }
+    if moras.is_empty() {
+        return Err(error(offset, "accent phrase must not be empty"));
+    }
+    let accent = accent.ok_or_else(|| error(offset, "accent not found in accent phrase"))?;
+    Ok(AccentPhrase {
+        moras,
+        accent,
+        is_interrogative,
+        pause: false,
+    })
+}
+
+/// Parse AquesTalk-like kana notation, e.g. `コンニチワ'/キョ'ウワ`
+///
+/// Accent phrases are separated by `/`, or by `、` to insert a pause.
+/// Each phrase must contain exactly one `'` after its accent nucleus.
+pub fn parse_kana(text: &str) -> Result<Vec<AccentPhrase>> {
+    let chars: Vec<(usize, char)> = text.chars().enumerate().collect();
+    let mut phrases = Vec::new();
+    let mut start = 0;
+    for (i, &(_, c)) in chars.iter().enumerate() {
+        if c == PHRASE_DELIMITER || c == PAUSE_DELIMITER {
+            let mut phrase = parse_phrase(&chars[start..i], start)?;
+            phrase.pause = c == PAUSE_DELIMITER;
+            phrases.push(phrase);
+            start = i + 1;
+        }
+    }
+    if start < chars.len() || phrases.is_empty() {
+        phrases.push(parse_phrase(&chars[start..], start)?);
+    }
+    Ok(phrases)
+}
+
+/// Convert accent phrases back to kana notation
+pub fn to_kana(phrases: &[AccentPhrase]) -> String {
+    let mut text = String::new();
+    for (i, phrase) in phrases.iter().enumerate() {
+        for (j, mora) in phrase.moras.iter().enumerate() {
+            text += &mora.kana;
+            if j + 1 == phrase.accent {
+                text.push(ACCENT_SYMBOL);
+            }
+        }
+        if phrase.is_interrogative {
+            text.push(WIDE_INTERROGATION_MARK);
+        }
+        if phrase.pause {
+            text.push(PAUSE_DELIMITER);
+        } else if i != phrases.len() - 1 {
+            text.push(PHRASE_DELIMITER);
+        }
+    }
+    text
+}
+
+/// Convert accent phrases to phones, tones, word2ph and the text for BERT
+///
+/// Every character of the returned text owns the phones of its mora, so the
+/// result can be fed to BERT in the same way as the output of `JTalkProcess::g2p`.
+pub fn phrases_to_phones(phrases: &[AccentPhrase]) -> (Vec<String>, Vec<i32>, Vec<i32>, String) {
+    let mut phones = vec!["_".to_string()];
+    let mut tones = vec![0];
+    let mut word2ph = vec![1];
+    let mut text = String::new();
+    for phrase in phrases {
+        for (mora, tone) in phrase.moras.iter().zip(phrase.tones()) {
+            let mut n_phone = 1;
+            if let Some(consonant) = &mora.consonant {
+                phones.push(consonant.clone());
+                tones.push(tone);
+                n_phone += 1;
+            }
+            phones.push(mora.vowel.clone());
+            tones.push(tone);
+            let n_char = mora.kana.chars().count() as i32;
+            word2ph.append(&mut crate::jtalk::JTalkProcess::distribute_phone(
+                n_phone, n_char,
+            ));
+            text += &mora.kana;
+        }
+        let mut punctuations = vec![];
+        if phrase.is_interrogative {
+            punctuations.push("?");
+        }
+        if phrase.pause {
+            punctuations.push(",");
+        }
+        for punctuation in punctuations {
+            phones.push(punctuation.to_string());
+            tones.push(0);
+            word2ph.push(1);
+            text += punctuation;
+        }
+    }
+    phones.push("_".to_string());
+    tones.push(0);
+    word2ph.push(1);
+    (phones, tones, word2ph, text)
+}
@@ -1,14 +1,27 @@
+pub mod analysis;
+#[cfg(feature = "std")]
+pub mod backend;
 #[cfg(feature = "std")]
 pub mod bert;
+pub mod bert_cache;
+pub mod english;
 pub mod error;
 pub mod jtalk;
+pub mod kana;
 #[cfg(feature = "std")]
 pub mod model;
 pub mod mora;
 pub mod nlp;
 pub mod norm;
+pub mod preprocess;
+#[cfg(feature = "quantize")]
+pub mod quantize;
+pub mod romaji;
 pub mod sbv2file;
+pub mod speaker;
 pub mod style;
+pub mod symbols;
+pub mod textnorm;
 pub mod tokenizer;
 #[cfg(feature = "std")]
 pub mod tts;
@@ -14,6 +14,7 @@ fn main_inner() -> anyhow::Result<()> {
         env::var("HOLDER_MAX_LOADED_MODElS")
             .ok()
             .and_then(|x| x.parse().ok()),
+        sbv2_core::model::SessionConfig::default(),
     )?;
     let mp = env::var("MODEL_PATH")?;
     let b = fs::read(&mp)?;
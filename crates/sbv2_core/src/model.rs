@@ -1,57 +1,146 @@
-use crate::error::Result;
-use ndarray::{array, Array1, Array2, Array3, Axis, Ix3};
-use ort::session::{builder::GraphOptimizationLevel, Session};
+use crate::backend::{take_output, Backend, InferenceBackend, TensorData};
+use crate::error::{Error, Result};
+use ndarray::{arr1, s, Array1, Array2, Array3, ArrayD, Axis, Ix3};
+use std::path::PathBuf;
+use std::str::FromStr;
 
-#[allow(clippy::vec_init_then_push, unused_variables)]
-pub fn load_model<P: AsRef<[u8]>>(model_file: P, bert: bool) -> Result<Session> {
-    let mut exp = Vec::new();
-    #[cfg(feature = "tensorrt")]
-    {
-        if bert {
-            exp.push(
-                ort::execution_providers::TensorRTExecutionProvider::default()
-                    .with_fp16(true)
-                    .with_profile_min_shapes("input_ids:1x1,attention_mask:1x1")
-                    .with_profile_max_shapes("input_ids:1x100,attention_mask:1x100")
-                    .with_profile_opt_shapes("input_ids:1x25,attention_mask:1x25")
-                    .build(),
-            );
+pub use crate::backend::load_model;
+
+/// Graph optimization level of ONNX Runtime, in increasing order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OptimizationLevel {
+    Disable,
+    Basic,
+    Extended,
+    All,
+}
+
+impl FromStr for OptimizationLevel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "disable" | "0" => Ok(Self::Disable),
+            "basic" | "1" => Ok(Self::Basic),
+            "extended" | "2" => Ok(Self::Extended),
+            "all" | "3" => Ok(Self::All),
+            _ => Err(Error::ValueError(format!(
+                "unknown optimization level: {s}"
+            ))),
         }
     }
-    #[cfg(feature = "cuda")]
-    {
-        #[allow(unused_mut)]
-        let mut cuda = ort::execution_providers::CUDAExecutionProvider::default();
-        #[cfg(feature = "cuda_tf32")]
-        {
-            cuda = cuda.with_tf32(true);
+}
+
+/// Model loading options
+///
+/// # Fields
+/// - `backend`: Inference backend, the other fields only apply to ONNX Runtime
+/// - `intra_threads`: Threads used inside an operator
+/// - `inter_threads`: Threads used across operators, only with `parallel_execution`
+/// - `optimization_level`: Graph optimization level
+/// - `parallel_execution`: Run independent operators in parallel instead of sequentially
+/// - `memory_pattern`: Preallocate memory from the shapes of previous runs
+/// - `cpu_arena`: Use an arena allocator for the CPU execution provider
+/// - `optimized_model_dir`: Directory the optimized graphs are written to, as `<name>.onnx`
+/// - `model_cache_dir`: Directory of optimized graphs reused across starts, keyed by a hash of the model.
+///   Graphs are cached at `Extended` at most, so they do not depend on the CPU, and optimized
+///   further to `optimization_level` when loaded. Cannot be combined with `optimized_model_dir`
+///
+/// The default uses every physical core, like before.
+///
+/// # Examples
+///
+/// ```rs
+/// let config = SessionConfig {
+///     intra_threads: 2,
+///     inter_threads: 1,
+///     parallel_execution: false,
+///     ..Default::default()
+/// };
+/// let mut tts_holder = TTSModelHolder::new(bert, tokenizer, None, config)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct SessionConfig {
+    pub backend: Backend,
+    pub intra_threads: usize,
+    pub inter_threads: usize,
+    pub optimization_level: OptimizationLevel,
+    pub parallel_execution: bool,
+    pub memory_pattern: bool,
+    pub cpu_arena: bool,
+    pub optimized_model_dir: Option<PathBuf>,
+    pub model_cache_dir: Option<PathBuf>,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        SessionConfig {
+            backend: Backend::default(),
+            intra_threads: num_cpus::get_physical(),
+            inter_threads: num_cpus::get_physical(),
+            optimization_level: OptimizationLevel::All,
+            parallel_execution: true,
+            memory_pattern: true,
+            cpu_arena: true,
+            optimized_model_dir: None,
+            model_cache_dir: None,
         }
-        exp.push(cuda.build());
     }
-    #[cfg(feature = "directml")]
-    {
-        exp.push(ort::execution_providers::DirectMLExecutionProvider::default().build());
+}
+
+/// Input layout of a VITS2 export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelVariant {
+    /// JP-Extra, with a single `bert` input of Japanese features
+    JpExtra,
+    /// Style-Bert-VITS2 2.x, with `bert` (Chinese), `ja_bert` and `en_bert` inputs
+    Multilingual,
+}
+
+impl ModelVariant {
+    /// Detect the variant from the input names of a loaded model
+    pub fn detect(session: &dyn InferenceBackend) -> Result<Self> {
+        let inputs = session.inputs();
+        let has = |name: &str| inputs.iter().any(|x| x == name);
+        if has("bert") && has("ja_bert") && has("en_bert") {
+            Ok(ModelVariant::Multilingual)
+        } else if has("bert") {
+            Ok(ModelVariant::JpExtra)
+        } else {
+            Err(Error::ValueError(format!(
+                "unknown VITS2 model with inputs {inputs:?}"
+            )))
+        }
     }
-    #[cfg(feature = "coreml")]
-    {
-        exp.push(ort::execution_providers::CoreMLExecutionProvider::default().build());
+
+    /// BERT inputs for Japanese features, the other languages are zero-filled
+    pub fn bert_inputs(self, bert: ArrayD<f32>) -> Vec<(&'static str, TensorData)> {
+        match self {
+            ModelVariant::JpExtra => vec![("bert", bert.into())],
+            ModelVariant::Multilingual => {
+                let zeros = ArrayD::<f32>::zeros(bert.raw_dim());
+                vec![
+                    ("bert", zeros.clone().into()),
+                    ("ja_bert", bert.into()),
+                    ("en_bert", zeros.into()),
+                ]
+            }
+        }
     }
-    exp.push(ort::execution_providers::CPUExecutionProvider::default().build());
-    Ok(Session::builder()?
-        .with_execution_providers(exp)?
-        .with_optimization_level(GraphOptimizationLevel::Level3)?
-        .with_intra_threads(num_cpus::get_physical())?
-        .with_parallel_execution(true)?
-        .with_inter_threads(num_cpus::get_physical())?
-        .commit_from_memory(model_file.as_ref())?)
+}
+
+/// Scalar float input
+fn scalar(value: f32) -> TensorData {
+    arr1(&[value]).into_dyn().into()
 }
 
 #[allow(clippy::too_many_arguments)]
 pub fn synthesize(
-    session: &mut Session,
+    session: &mut dyn InferenceBackend,
+    variant: ModelVariant,
     bert_ori: Array2<f32>,
     x_tst: Array1<i64>,
-    mut spk_ids: Array1<i64>,
+    spk_ids: Array1<i64>,
     tones: Array1<i64>,
     lang_ids: Array1<i64>,
     style_vector: Array1<f32>,
@@ -60,47 +149,109 @@ pub fn synthesize(
     noise_scale: f32,
     noise_scale_w: f32,
 ) -> Result<Array3<f32>> {
-    let bert_ori = bert_ori.insert_axis(Axis(0));
-    let bert_ori = bert_ori.as_standard_layout();
-    let bert = ort::value::TensorRef::from_array_view(&bert_ori)?;
-    let mut x_tst_lengths = array![x_tst.shape()[0] as i64];
-    let x_tst_lengths = ort::value::TensorRef::from_array_view(&mut x_tst_lengths)?;
-    let mut x_tst = x_tst.insert_axis(Axis(0));
-    let x_tst = ort::value::TensorRef::from_array_view(&mut x_tst)?;
-    let mut lang_ids = lang_ids.insert_axis(Axis(0));
-    let lang_ids = ort::value::TensorRef::from_array_view(&mut lang_ids)?;
-    let mut tones = tones.insert_axis(Axis(0));
-    let tones = ort::value::TensorRef::from_array_view(&mut tones)?;
-    let mut style_vector = style_vector.insert_axis(Axis(0));
-    let style_vector = ort::value::TensorRef::from_array_view(&mut style_vector)?;
-    let sid = ort::value::TensorRef::from_array_view(&mut spk_ids)?;
-    let sdp_ratio = vec![sdp_ratio];
-    let sdp_ratio = ort::value::TensorRef::from_array_view((vec![1_i64], sdp_ratio.as_slice()))?;
-    let length_scale = vec![length_scale];
-    let length_scale =
-        ort::value::TensorRef::from_array_view((vec![1_i64], length_scale.as_slice()))?;
-    let noise_scale = vec![noise_scale];
-    let noise_scale =
-        ort::value::TensorRef::from_array_view((vec![1_i64], noise_scale.as_slice()))?;
-    let noise_scale_w = vec![noise_scale_w];
-    let noise_scale_w =
-        ort::value::TensorRef::from_array_view((vec![1_i64], noise_scale_w.as_slice()))?;
-    let outputs = session.run(ort::inputs! {
-        "x_tst" =>  x_tst,
-        "x_tst_lengths" => x_tst_lengths,
-        "sid" => sid,
-        "tones" => tones,
-        "language" => lang_ids,
-        "bert" => bert,
-        "style_vec" => style_vector,
-        "sdp_ratio" => sdp_ratio,
-        "length_scale" => length_scale,
-        "noise_scale" => noise_scale,
-        "noise_scale_w" => noise_scale_w,
-    })?;
-    let audio_array = outputs["output"]
-        .try_extract_array::<f32>()?
-        .into_dimensionality::<Ix3>()?
-        .to_owned();
+    let x_tst_lengths = arr1(&[x_tst.len() as i64]);
+    let mut inputs = variant.bert_inputs(bert_ori.insert_axis(Axis(0)).into_dyn());
+    inputs.extend([
+        ("x_tst", x_tst.insert_axis(Axis(0)).into_dyn().into()),
+        ("x_tst_lengths", x_tst_lengths.into_dyn().into()),
+        ("sid", spk_ids.into_dyn().into()),
+        ("tones", tones.insert_axis(Axis(0)).into_dyn().into()),
+        ("language", lang_ids.insert_axis(Axis(0)).into_dyn().into()),
+        (
+            "style_vec",
+            style_vector.insert_axis(Axis(0)).into_dyn().into(),
+        ),
+        ("sdp_ratio", scalar(sdp_ratio)),
+        ("length_scale", scalar(length_scale)),
+        ("noise_scale", scalar(noise_scale)),
+        ("noise_scale_w", scalar(noise_scale_w)),
+    ]);
+    let mut outputs = session.run(inputs)?;
+    let audio_array = take_output(&mut outputs, "output")?
+        .into_f32()?
+        .into_dimensionality::<Ix3>()?;
     Ok(audio_array)
 }
+
+/// Whether `session` can synthesize several sentences in one run
+///
+/// Batched models have to output `y_lengths`, the number of valid samples of each audio,
+/// see `convert_model.py --batch`.
+pub fn supports_batch(session: &dyn InferenceBackend) -> bool {
+    session.outputs().iter().any(|x| x == "y_lengths")
+}
+
+/// Synthesize several sentences in one run
+///
+/// Each input is `(bert_ori, x_tst, tones, lang_ids)` as returned by `PreparedText::into_input`.
+/// The inputs are padded to the longest sentence and each audio is trimmed by `y_lengths`.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn synthesize_batch(
+    session: &mut dyn InferenceBackend,
+    variant: ModelVariant,
+    inputs: Vec<(Array2<f32>, Array1<i64>, Array1<i64>, Array1<i64>)>,
+    speaker_id: i64,
+    style_vector: Array1<f32>,
+    sdp_ratio: f32,
+    length_scale: f32,
+    noise_scale: f32,
+    noise_scale_w: f32,
+) -> Result<Vec<Array3<f32>>> {
+    if !supports_batch(session) {
+        return Err(Error::ValueError(
+            "model has no y_lengths output and cannot synthesize in batches".to_string(),
+        ));
+    }
+    let batch_size = inputs.len();
+    let max_len = inputs.iter().map(|x| x.1.len()).max().unwrap_or(0);
+    let hidden = inputs.first().map(|x| x.0.nrows()).unwrap_or(0);
+    let mut bert = Array3::<f32>::zeros((batch_size, hidden, max_len));
+    let mut x_tst = Array2::<i64>::zeros((batch_size, max_len));
+    let mut tones = Array2::<i64>::zeros((batch_size, max_len));
+    let mut lang_ids = Array2::<i64>::zeros((batch_size, max_len));
+    let mut x_tst_lengths = Array1::<i64>::zeros(batch_size);
+    for (i, (bert_ori, phones, tone, lang)) in inputs.iter().enumerate() {
+        let len = phones.len();
+        bert.slice_mut(s![i, .., ..len]).assign(bert_ori);
+        x_tst.slice_mut(s![i, ..len]).assign(phones);
+        tones.slice_mut(s![i, ..len]).assign(tone);
+        lang_ids.slice_mut(s![i, ..len]).assign(lang);
+        x_tst_lengths[i] = len as i64;
+    }
+    let style_len = style_vector.len();
+    let style_vector = style_vector
+        .insert_axis(Axis(0))
+        .broadcast((batch_size, style_len))
+        .ok_or_else(|| Error::ValueError("invalid style vector".to_string()))?
+        .to_owned();
+    let sid = Array1::from_elem(batch_size, speaker_id);
+    let mut inputs = variant.bert_inputs(bert.into_dyn());
+    inputs.extend([
+        ("x_tst", x_tst.into_dyn().into()),
+        ("x_tst_lengths", x_tst_lengths.into_dyn().into()),
+        ("sid", sid.into_dyn().into()),
+        ("tones", tones.into_dyn().into()),
+        ("language", lang_ids.into_dyn().into()),
+        ("style_vec", style_vector.into_dyn().into()),
+        ("sdp_ratio", scalar(sdp_ratio)),
+        ("length_scale", scalar(length_scale)),
+        ("noise_scale", scalar(noise_scale)),
+        ("noise_scale_w", scalar(noise_scale_w)),
+    ]);
+    let mut outputs = session.run(inputs)?;
+    let audio_array = take_output(&mut outputs, "output")?
+        .into_f32()?
+        .into_dimensionality::<Ix3>()?;
+    let y_lengths = take_output(&mut outputs, "y_lengths")?.into_i64()?;
+    let y_lengths: Vec<usize> = y_lengths.iter().map(|&x| x.max(0) as usize).collect();
+    Ok((0..batch_size)
+        .map(|i| {
+            let len = y_lengths
+                .get(i)
+                .copied()
+                .unwrap_or(usize::MAX)
+                .min(audio_array.shape()[2]);
+            audio_array.slice(s![i..i + 1, .., ..len]).to_owned()
+        })
+        .collect())
+}
@@ -1,3 +1,4 @@
+use crate::error::{Error, Result};
 use crate::norm::SYMBOLS;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
@@ -13,12 +14,22 @@ static SYMBOL_TO_ID: Lazy<HashMap<String, i32>> = Lazy::new(|| {
 pub fn cleaned_text_to_sequence(
     cleaned_phones: Vec<String>,
     tones: Vec<i32>,
-) -> (Vec<i64>, Vec<i64>, Vec<i64>) {
+) -> Result<(Vec<i64>, Vec<i64>, Vec<i64>)> {
     let phones: Vec<i64> = cleaned_phones
         .iter()
-        .map(|phone| *SYMBOL_TO_ID.get(phone).unwrap() as i64)
-        .collect();
+        .enumerate()
+        .map(|(i, phone)| {
+            SYMBOL_TO_ID
+                .get(phone)
+                .map(|id| *id as i64)
+                .ok_or_else(|| Error::UnknownPhoneError {
+                    word: phone.clone(),
+                    offset: i,
+                    phone: phone.clone(),
+                })
+        })
+        .collect::<Result<_>>()?;
     let tones: Vec<i64> = tones.iter().map(|tone| (*tone + 6) as i64).collect();
     let lang_ids: Vec<i64> = vec![1; phones.len()];
-    (phones, tones, lang_ids)
+    Ok((phones, tones, lang_ids))
 }
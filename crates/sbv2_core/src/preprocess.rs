@@ -0,0 +1,270 @@
+use crate::english::EnglishConverter;
+use crate::error::{Error, Result};
+use crate::symbols::SymbolReader;
+use crate::textnorm::TextNormalizer;
+use regex::Regex;
+use serde::Deserialize;
+
+/// A step that rewrites text before `num2word` and `norm::normalize_text`
+///
+/// # Examples
+///
+/// ```rs
+/// struct Shout;
+///
+/// impl TextPreprocessor for Shout {
+///     fn name(&self) -> &str {
+///         "shout"
+///     }
+///
+///     fn process(&self, text: &str) -> Result<String> {
+///         Ok(text.replace("。", "！"))
+///     }
+/// }
+///
+/// tts_holder.add_preprocessor(Box::new(Shout));
+/// ```
+pub trait TextPreprocessor: Send + Sync {
+    /// Name used to find the step in a `PreprocessPipeline`
+    fn name(&self) -> &str;
+
+    fn process(&self, text: &str) -> Result<String>;
+}
+
+impl TextPreprocessor for TextNormalizer {
+    fn name(&self) -> &str {
+        "normalizer"
+    }
+
+    fn process(&self, text: &str) -> Result<String> {
+        Ok(self.normalize(text))
+    }
+}
+
+impl TextPreprocessor for SymbolReader {
+    fn name(&self) -> &str {
+        "symbols"
+    }
+
+    fn process(&self, text: &str) -> Result<String> {
+        Ok(self.read(text))
+    }
+}
+
+impl TextPreprocessor for EnglishConverter {
+    fn name(&self) -> &str {
+        "english"
+    }
+
+    fn process(&self, text: &str) -> Result<String> {
+        Ok(self.convert(text))
+    }
+}
+
+/// A replacement rule in a rule file
+///
+/// # Fields
+/// - `pattern`: Text to find, or a regular expression when `regex` is true
+/// - `replacement`: Replacement text, may refer to capture groups as `$1` when `regex` is true
+/// - `regex`: Whether `pattern` is a regular expression
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplaceRule {
+    pub pattern: String,
+    pub replacement: String,
+    #[serde(default)]
+    pub regex: bool,
+}
+
+#[derive(Deserialize)]
+struct RuleFile {
+    #[serde(default = "rule_file_name_default")]
+    name: String,
+    rules: Vec<ReplaceRule>,
+}
+
+fn rule_file_name_default() -> String {
+    "rules".to_string()
+}
+
+enum Matcher {
+    Literal(String),
+    Regex(Regex),
+}
+
+/// Literal and regex replacements applied in order
+///
+/// Rule files are JSON:
+///
+/// ```json
+/// {
+///     "name": "brands",
+///     "rules": [
+///         { "pattern": "SBV2", "replacement": "スタイルバートビッツツー" },
+///         { "pattern": "[\\u{1F300}-\\u{1FAFF}]", "replacement": "", "regex": true }
+///     ]
+/// }
+/// ```
+pub struct RuleSet {
+    name: String,
+    rules: Vec<(Matcher, String)>,
+}
+
+impl RuleSet {
+    pub fn new(name: &str, rules: Vec<ReplaceRule>) -> Result<Self> {
+        let rules = rules
+            .into_iter()
+            .map(|rule| {
+                let matcher = if rule.regex {
+                    Matcher::Regex(Regex::new(&rule.pattern)?)
+                } else {
+                    Matcher::Literal(rule.pattern)
+                };
+                Ok((matcher, rule.replacement))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            name: name.to_string(),
+            rules,
+        })
+    }
+
+    /// Load a rule file
+    pub fn from_json<P: AsRef<[u8]>>(json: P) -> Result<Self> {
+        let file: RuleFile = serde_json::from_slice(json.as_ref())?;
+        Self::new(&file.name, file.rules)
+    }
+}
+
+impl TextPreprocessor for RuleSet {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn process(&self, text: &str) -> Result<String> {
+        let mut text = text.to_string();
+        for (matcher, replacement) in &self.rules {
+            text = match matcher {
+                Matcher::Literal(pattern) => text.replace(pattern, replacement),
+                Matcher::Regex(regex) => regex.replace_all(&text, replacement).to_string(),
+            };
+        }
+        Ok(text)
+    }
+}
+
+enum PreprocessStep {
+    Normalizer,
+    Symbols,
+    English,
+    Custom(Box<dyn TextPreprocessor>),
+}
+
+/// Ordered text preprocessing steps
+///
+/// By default the pipeline runs the built-in `normalizer`, `symbols` and `english` steps.
+/// Their settings are available as fields, while custom steps are added with
+/// `push`, `insert_before` or `load_rules`.
+pub struct PreprocessPipeline {
+    pub normalizer: TextNormalizer,
+    pub symbols: SymbolReader,
+    pub english: EnglishConverter,
+    steps: Vec<PreprocessStep>,
+}
+
+impl Default for PreprocessPipeline {
+    fn default() -> Self {
+        Self {
+            normalizer: TextNormalizer::default(),
+            symbols: SymbolReader::new(),
+            english: EnglishConverter::new(),
+            steps: vec![
+                PreprocessStep::Normalizer,
+                PreprocessStep::Symbols,
+                PreprocessStep::English,
+            ],
+        }
+    }
+}
+
+impl PreprocessPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn step_name<'a>(&'a self, step: &'a PreprocessStep) -> &'a str {
+        match step {
+            PreprocessStep::Normalizer => self.normalizer.name(),
+            PreprocessStep::Symbols => self.symbols.name(),
+            PreprocessStep::English => self.english.name(),
+            PreprocessStep::Custom(preprocessor) => preprocessor.name(),
+        }
+    }
+
+    fn position(&self, name: &str) -> Result<usize> {
+        self.steps
+            .iter()
+            .position(|step| self.step_name(step) == name)
+            .ok_or_else(|| Error::PreprocessError(format!("step `{name}` not found")))
+    }
+
+    /// Names of the steps in the order they run
+    pub fn names(&self) -> Vec<String> {
+        self.steps
+            .iter()
+            .map(|step| self.step_name(step).to_string())
+            .collect()
+    }
+
+    /// Add a step at the end of the pipeline
+    pub fn push(&mut self, preprocessor: Box<dyn TextPreprocessor>) {
+        self.steps.push(PreprocessStep::Custom(preprocessor));
+    }
+
+    /// Add a step before the step named `name`
+    pub fn insert_before(
+        &mut self,
+        name: &str,
+        preprocessor: Box<dyn TextPreprocessor>,
+    ) -> Result<()> {
+        let index = self.position(name)?;
+        self.steps
+            .insert(index, PreprocessStep::Custom(preprocessor));
+        Ok(())
+    }
+
+    /// Remove the step named `name`, built-in steps included
+    pub fn remove(&mut self, name: &str) -> Result<()> {
+        let index = self.position(name)?;
+        self.steps.remove(index);
+        Ok(())
+    }
+
+    /// Load a rule file as a step that runs before the built-in steps
+    ///
+    /// Rule files run in the order they were loaded.
+    pub fn load_rules<P: AsRef<[u8]>>(&mut self, json: P) -> Result<()> {
+        let rules = RuleSet::from_json(json)?;
+        let index = self
+            .steps
+            .iter()
+            .position(|step| !matches!(step, PreprocessStep::Custom(_)))
+            .unwrap_or(self.steps.len());
+        self.steps
+            .insert(index, PreprocessStep::Custom(Box::new(rules)));
+        Ok(())
+    }
+
+    /// Run every step in order
+    pub fn process(&self, text: &str) -> Result<String> {
+        let mut text = text.to_string();
+        for step in &self.steps {
+            text = match step {
+                PreprocessStep::Normalizer => self.normalizer.process(&text)?,
+                PreprocessStep::Symbols => self.symbols.process(&text)?,
+                PreprocessStep::English => self.english.process(&text)?,
+                PreprocessStep::Custom(preprocessor) => preprocessor.process(&text)?,
+            };
+        }
+        Ok(text)
+    }
+}
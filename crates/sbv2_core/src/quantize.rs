@@ -0,0 +1,540 @@
+//! Dynamic INT8 quantization of ONNX models
+//!
+//! Every `MatMul` whose weight is a constant float32 matrix is rewritten into
+//! `DynamicQuantizeLinear` → `MatMulInteger` → `Cast` → `Mul`, the same graph
+//! `onnxruntime.quantization.quantize_dynamic` produces, so no Python is needed.
+//! Weights are quantized symmetrically per tensor.
+use crate::error::{Error, Result};
+use prost::Message;
+use std::collections::{HashMap, HashSet};
+
+/// Subset of `onnx.proto` covering the messages a model can contain
+///
+/// prost skips fields that are not declared here when decoding, so they are dropped
+/// from the quantized model.
+pub mod onnx {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ModelProto {
+        #[prost(int64, tag = "1")]
+        pub ir_version: i64,
+        #[prost(message, repeated, tag = "8")]
+        pub opset_import: Vec<OperatorSetIdProto>,
+        #[prost(string, tag = "2")]
+        pub producer_name: String,
+        #[prost(string, tag = "3")]
+        pub producer_version: String,
+        #[prost(string, tag = "4")]
+        pub domain: String,
+        #[prost(int64, tag = "5")]
+        pub model_version: i64,
+        #[prost(string, tag = "6")]
+        pub doc_string: String,
+        #[prost(message, optional, tag = "7")]
+        pub graph: Option<GraphProto>,
+        #[prost(message, repeated, tag = "14")]
+        pub metadata_props: Vec<StringStringEntryProto>,
+        #[prost(message, repeated, tag = "20")]
+        pub training_info: Vec<TrainingInfoProto>,
+        #[prost(message, repeated, tag = "25")]
+        pub functions: Vec<FunctionProto>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct OperatorSetIdProto {
+        #[prost(string, tag = "1")]
+        pub domain: String,
+        #[prost(int64, tag = "2")]
+        pub version: i64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StringStringEntryProto {
+        #[prost(string, tag = "1")]
+        pub key: String,
+        #[prost(string, tag = "2")]
+        pub value: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TensorAnnotation {
+        #[prost(string, tag = "1")]
+        pub tensor_name: String,
+        #[prost(message, repeated, tag = "2")]
+        pub quant_parameter_tensor_names: Vec<StringStringEntryProto>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GraphProto {
+        #[prost(message, repeated, tag = "1")]
+        pub node: Vec<NodeProto>,
+        #[prost(string, tag = "2")]
+        pub name: String,
+        #[prost(message, repeated, tag = "5")]
+        pub initializer: Vec<TensorProto>,
+        #[prost(message, repeated, tag = "15")]
+        pub sparse_initializer: Vec<SparseTensorProto>,
+        #[prost(string, tag = "10")]
+        pub doc_string: String,
+        #[prost(message, repeated, tag = "11")]
+        pub input: Vec<ValueInfoProto>,
+        #[prost(message, repeated, tag = "12")]
+        pub output: Vec<ValueInfoProto>,
+        #[prost(message, repeated, tag = "13")]
+        pub value_info: Vec<ValueInfoProto>,
+        #[prost(message, repeated, tag = "14")]
+        pub quantization_annotation: Vec<TensorAnnotation>,
+        #[prost(message, repeated, tag = "16")]
+        pub metadata_props: Vec<StringStringEntryProto>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct NodeProto {
+        #[prost(string, repeated, tag = "1")]
+        pub input: Vec<String>,
+        #[prost(string, repeated, tag = "2")]
+        pub output: Vec<String>,
+        #[prost(string, tag = "3")]
+        pub name: String,
+        #[prost(string, tag = "4")]
+        pub op_type: String,
+        #[prost(string, tag = "7")]
+        pub domain: String,
+        #[prost(string, tag = "8")]
+        pub overload: String,
+        #[prost(message, repeated, tag = "5")]
+        pub attribute: Vec<AttributeProto>,
+        #[prost(string, tag = "6")]
+        pub doc_string: String,
+        #[prost(message, repeated, tag = "9")]
+        pub metadata_props: Vec<StringStringEntryProto>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct AttributeProto {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(string, tag = "21")]
+        pub ref_attr_name: String,
+        #[prost(string, tag = "13")]
+        pub doc_string: String,
+        #[prost(int32, tag = "20")]
+        pub r#type: i32,
+        #[prost(float, tag = "2")]
+        pub f: f32,
+        #[prost(int64, tag = "3")]
+        pub i: i64,
+        #[prost(bytes = "vec", tag = "4")]
+        pub s: Vec<u8>,
+        #[prost(message, optional, tag = "5")]
+        pub t: Option<TensorProto>,
+        #[prost(message, optional, tag = "6")]
+        pub g: Option<GraphProto>,
+        #[prost(message, optional, tag = "22")]
+        pub sparse_tensor: Option<SparseTensorProto>,
+        #[prost(message, optional, tag = "14")]
+        pub tp: Option<TypeProto>,
+        #[prost(float, repeated, tag = "7")]
+        pub floats: Vec<f32>,
+        #[prost(int64, repeated, tag = "8")]
+        pub ints: Vec<i64>,
+        #[prost(bytes = "vec", repeated, tag = "9")]
+        pub strings: Vec<Vec<u8>>,
+        #[prost(message, repeated, tag = "10")]
+        pub tensors: Vec<TensorProto>,
+        #[prost(message, repeated, tag = "11")]
+        pub graphs: Vec<GraphProto>,
+        #[prost(message, repeated, tag = "23")]
+        pub sparse_tensors: Vec<SparseTensorProto>,
+        #[prost(message, repeated, tag = "15")]
+        pub type_protos: Vec<TypeProto>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ValueInfoProto {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(message, optional, tag = "2")]
+        pub r#type: Option<TypeProto>,
+        #[prost(string, tag = "3")]
+        pub doc_string: String,
+        #[prost(message, repeated, tag = "4")]
+        pub metadata_props: Vec<StringStringEntryProto>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TensorSegment {
+        #[prost(int64, tag = "1")]
+        pub begin: i64,
+        #[prost(int64, tag = "2")]
+        pub end: i64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TensorProto {
+        #[prost(int64, repeated, tag = "1")]
+        pub dims: Vec<i64>,
+        #[prost(int32, tag = "2")]
+        pub data_type: i32,
+        #[prost(message, optional, tag = "3")]
+        pub segment: Option<TensorSegment>,
+        #[prost(float, repeated, tag = "4")]
+        pub float_data: Vec<f32>,
+        #[prost(int32, repeated, tag = "5")]
+        pub int32_data: Vec<i32>,
+        #[prost(bytes = "vec", repeated, tag = "6")]
+        pub string_data: Vec<Vec<u8>>,
+        #[prost(int64, repeated, tag = "7")]
+        pub int64_data: Vec<i64>,
+        #[prost(string, tag = "8")]
+        pub name: String,
+        #[prost(string, tag = "12")]
+        pub doc_string: String,
+        #[prost(bytes = "vec", tag = "9")]
+        pub raw_data: Vec<u8>,
+        #[prost(message, repeated, tag = "13")]
+        pub external_data: Vec<StringStringEntryProto>,
+        #[prost(int32, tag = "14")]
+        pub data_location: i32,
+        #[prost(double, repeated, tag = "10")]
+        pub double_data: Vec<f64>,
+        #[prost(uint64, repeated, tag = "11")]
+        pub uint64_data: Vec<u64>,
+        #[prost(message, repeated, tag = "16")]
+        pub metadata_props: Vec<StringStringEntryProto>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SparseTensorProto {
+        #[prost(message, optional, tag = "1")]
+        pub values: Option<TensorProto>,
+        #[prost(message, optional, tag = "2")]
+        pub indices: Option<TensorProto>,
+        #[prost(int64, repeated, tag = "3")]
+        pub dims: Vec<i64>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TensorShapeProto {
+        #[prost(message, repeated, tag = "1")]
+        pub dim: Vec<Dimension>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Dimension {
+        #[prost(oneof = "DimensionValue", tags = "1, 2")]
+        pub value: Option<DimensionValue>,
+        #[prost(string, tag = "3")]
+        pub denotation: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum DimensionValue {
+        #[prost(int64, tag = "1")]
+        DimValue(i64),
+        #[prost(string, tag = "2")]
+        DimParam(String),
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TypeProto {
+        #[prost(oneof = "TypeValue", tags = "1, 4, 5, 9, 8")]
+        pub value: Option<TypeValue>,
+        #[prost(string, tag = "6")]
+        pub denotation: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum TypeValue {
+        #[prost(message, tag = "1")]
+        TensorType(TensorTypeProto),
+        #[prost(message, tag = "4")]
+        SequenceType(Box<SequenceTypeProto>),
+        #[prost(message, tag = "5")]
+        MapType(Box<MapTypeProto>),
+        #[prost(message, tag = "9")]
+        OptionalType(Box<OptionalTypeProto>),
+        #[prost(message, tag = "8")]
+        SparseTensorType(TensorTypeProto),
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TensorTypeProto {
+        #[prost(int32, tag = "1")]
+        pub elem_type: i32,
+        #[prost(message, optional, tag = "2")]
+        pub shape: Option<TensorShapeProto>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SequenceTypeProto {
+        #[prost(message, optional, boxed, tag = "1")]
+        pub elem_type: Option<Box<TypeProto>>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct MapTypeProto {
+        #[prost(int32, tag = "1")]
+        pub key_type: i32,
+        #[prost(message, optional, boxed, tag = "2")]
+        pub value_type: Option<Box<TypeProto>>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct OptionalTypeProto {
+        #[prost(message, optional, boxed, tag = "1")]
+        pub elem_type: Option<Box<TypeProto>>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct FunctionProto {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(string, repeated, tag = "4")]
+        pub input: Vec<String>,
+        #[prost(string, repeated, tag = "5")]
+        pub output: Vec<String>,
+        #[prost(string, repeated, tag = "6")]
+        pub attribute: Vec<String>,
+        #[prost(message, repeated, tag = "11")]
+        pub attribute_proto: Vec<AttributeProto>,
+        #[prost(message, repeated, tag = "7")]
+        pub node: Vec<NodeProto>,
+        #[prost(string, tag = "8")]
+        pub doc_string: String,
+        #[prost(message, repeated, tag = "9")]
+        pub opset_import: Vec<OperatorSetIdProto>,
+        #[prost(string, tag = "10")]
+        pub domain: String,
+        #[prost(string, tag = "13")]
+        pub overload: String,
+        #[prost(message, repeated, tag = "12")]
+        pub value_info: Vec<ValueInfoProto>,
+        #[prost(message, repeated, tag = "14")]
+        pub metadata_props: Vec<StringStringEntryProto>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TrainingInfoProto {
+        #[prost(message, optional, tag = "1")]
+        pub initialization: Option<GraphProto>,
+        #[prost(message, optional, tag = "2")]
+        pub algorithm: Option<GraphProto>,
+        #[prost(message, repeated, tag = "3")]
+        pub initialization_binding: Vec<StringStringEntryProto>,
+        #[prost(message, repeated, tag = "4")]
+        pub update_binding: Vec<StringStringEntryProto>,
+    }
+}
+
+use onnx::{AttributeProto, GraphProto, ModelProto, NodeProto, TensorProto};
+
+const FLOAT: i32 = 1;
+const INT8: i32 = 3;
+const ATTRIBUTE_INT: i32 = 2;
+const EXTERNAL: i32 = 1;
+/// `DynamicQuantizeLinear` needs opset 11
+const MIN_OPSET: i64 = 11;
+
+/// Number of `MatMul` nodes rewritten by `quantize_dynamic`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuantizeStats {
+    pub quantized: usize,
+    pub skipped: usize,
+}
+
+fn float_values(tensor: &TensorProto) -> Option<Vec<f32>> {
+    if tensor.data_type != FLOAT || tensor.data_location == EXTERNAL {
+        return None;
+    }
+    if !tensor.raw_data.is_empty() {
+        Some(
+            tensor
+                .raw_data
+                .chunks_exact(4)
+                .map(|x| f32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+                .collect(),
+        )
+    } else {
+        Some(tensor.float_data.clone())
+    }
+}
+
+fn scalar(name: String, data_type: i32, raw_data: Vec<u8>) -> TensorProto {
+    TensorProto {
+        name,
+        data_type,
+        raw_data,
+        ..Default::default()
+    }
+}
+
+/// Quantize `values` to int8 with a zero point of 0, returning the data and the scale
+fn quantize_weight(values: &[f32]) -> (Vec<u8>, f32) {
+    let max = values.iter().fold(0.0_f32, |max, x| max.max(x.abs()));
+    let scale = if max > 0.0 { max / 127.0 } else { 1.0 };
+    let data = values
+        .iter()
+        .map(|x| (x / scale).round().clamp(-127.0, 127.0) as i8 as u8)
+        .collect();
+    (data, scale)
+}
+
+fn node(op_type: &str, name: String, input: Vec<String>, output: Vec<String>) -> NodeProto {
+    NodeProto {
+        op_type: op_type.to_string(),
+        name,
+        input,
+        output,
+        ..Default::default()
+    }
+}
+
+fn collect_inputs(graph: &GraphProto, used: &mut HashSet<String>) {
+    for node in &graph.node {
+        used.extend(node.input.iter().cloned());
+        for attribute in &node.attribute {
+            for graph in attribute.g.iter().chain(&attribute.graphs) {
+                collect_inputs(graph, used);
+            }
+        }
+    }
+    used.extend(graph.output.iter().map(|x| x.name.clone()));
+}
+
+fn quantize_graph(graph: &mut GraphProto) -> QuantizeStats {
+    let mut stats = QuantizeStats::default();
+    let graph_inputs: HashSet<String> = graph.input.iter().map(|x| x.name.clone()).collect();
+    let weights: HashMap<String, usize> = graph
+        .initializer
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| x.dims.len() == 2 && !graph_inputs.contains(&x.name))
+        .map(|(i, x)| (x.name.clone(), i))
+        .collect();
+    // Quantized weight and scale names, shared by every MatMul using the same weight
+    let mut quantized: HashMap<String, (String, String)> = HashMap::new();
+    let mut new_initializers = vec![];
+    let mut nodes = Vec::with_capacity(graph.node.len());
+    for old in std::mem::take(&mut graph.node) {
+        let weight = match (old.op_type.as_str(), old.domain.as_str(), old.input.get(1)) {
+            ("MatMul", "", Some(weight)) => weight.clone(),
+            _ => {
+                nodes.push(old);
+                continue;
+            }
+        };
+        if !quantized.contains_key(&weight) {
+            let values = weights
+                .get(&weight)
+                .and_then(|&i| float_values(&graph.initializer[i]));
+            let Some(values) = values else {
+                stats.skipped += 1;
+                nodes.push(old);
+                continue;
+            };
+            let (data, scale) = quantize_weight(&values);
+            let dims = graph.initializer[weights[&weight]].dims.clone();
+            let names = (format!("{weight}_quantized"), format!("{weight}_scale"));
+            new_initializers.push(TensorProto {
+                name: names.0.clone(),
+                dims,
+                data_type: INT8,
+                raw_data: data,
+                ..Default::default()
+            });
+            new_initializers.push(scalar(format!("{weight}_zero_point"), INT8, vec![0]));
+            new_initializers.push(scalar(names.1.clone(), FLOAT, scale.to_le_bytes().to_vec()));
+            quantized.insert(weight.clone(), names);
+        }
+        let (weight_q, weight_scale) = &quantized[&weight];
+        let input = &old.input[0];
+        let output = &old.output[0];
+        let prefix = if old.name.is_empty() {
+            output.clone()
+        } else {
+            old.name.clone()
+        };
+        let t = |suffix: &str| format!("{prefix}_{suffix}");
+        nodes.push(node(
+            "DynamicQuantizeLinear",
+            t("quantize"),
+            vec![input.clone()],
+            vec![t("input_q"), t("input_scale"), t("input_zero_point")],
+        ));
+        nodes.push(node(
+            "MatMulInteger",
+            t("matmul"),
+            vec![
+                t("input_q"),
+                weight_q.clone(),
+                t("input_zero_point"),
+                format!("{weight}_zero_point"),
+            ],
+            vec![t("output_int32")],
+        ));
+        let mut cast = node(
+            "Cast",
+            t("cast"),
+            vec![t("output_int32")],
+            vec![t("output_float")],
+        );
+        cast.attribute.push(AttributeProto {
+            name: "to".to_string(),
+            r#type: ATTRIBUTE_INT,
+            i: FLOAT as i64,
+            ..Default::default()
+        });
+        nodes.push(cast);
+        nodes.push(node(
+            "Mul",
+            t("scale"),
+            vec![t("input_scale"), weight_scale.clone()],
+            vec![t("output_scale")],
+        ));
+        nodes.push(node(
+            "Mul",
+            t("output"),
+            vec![t("output_float"), t("output_scale")],
+            vec![output.clone()],
+        ));
+        stats.quantized += 1;
+    }
+    graph.node = nodes;
+    graph.initializer.extend(new_initializers);
+    // Drop the float weights nothing refers to anymore
+    let mut used = HashSet::new();
+    collect_inputs(graph, &mut used);
+    graph
+        .initializer
+        .retain(|x| used.contains(&x.name) || graph_inputs.contains(&x.name));
+    stats
+}
+
+/// Quantize the constant `MatMul` weights of an ONNX model to INT8
+///
+/// # Examples
+///
+/// ```rs
+/// let (quantized, stats) = quantize_dynamic(&fs::read("model.onnx")?)?;
+/// fs::write("model.int8.onnx", quantized)?;
+/// ```
+pub fn quantize_dynamic(model: &[u8]) -> Result<(Vec<u8>, QuantizeStats)> {
+    let mut model = ModelProto::decode(model)?;
+    let opset = model
+        .opset_import
+        .iter()
+        .find(|x| x.domain.is_empty() || x.domain == "ai.onnx")
+        .map(|x| x.version)
+        .unwrap_or(0);
+    if opset < MIN_OPSET {
+        return Err(Error::ValueError(format!(
+            "opset {opset} is too old for dynamic quantization (needs {MIN_OPSET})"
+        )));
+    }
+    let graph = model
+        .graph
+        .as_mut()
+        .ok_or_else(|| Error::ValueError("model has no graph".to_string()))?;
+    let stats = quantize_graph(graph);
+    Ok((model.encode_to_vec(), stats))
+}
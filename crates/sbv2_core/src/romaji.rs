@@ -0,0 +1,187 @@
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+
+/// Hepburn and Kunrei syllables, the longest match is read first
+const SYLLABLES: &[(&str, &str)] = &[
+    ("a", "ア"),
+    ("i", "イ"),
+    ("u", "ウ"),
+    ("e", "エ"),
+    ("o", "オ"),
+    ("ka", "カ"),
+    ("ki", "キ"),
+    ("ku", "ク"),
+    ("ke", "ケ"),
+    ("ko", "コ"),
+    ("kya", "キャ"),
+    ("kyu", "キュ"),
+    ("kyo", "キョ"),
+    ("ga", "ガ"),
+    ("gi", "ギ"),
+    ("gu", "グ"),
+    ("ge", "ゲ"),
+    ("go", "ゴ"),
+    ("gya", "ギャ"),
+    ("gyu", "ギュ"),
+    ("gyo", "ギョ"),
+    ("sa", "サ"),
+    ("si", "シ"),
+    ("shi", "シ"),
+    ("su", "ス"),
+    ("se", "セ"),
+    ("so", "ソ"),
+    ("sha", "シャ"),
+    ("shu", "シュ"),
+    ("she", "シェ"),
+    ("sho", "ショ"),
+    ("sya", "シャ"),
+    ("syu", "シュ"),
+    ("syo", "ショ"),
+    ("za", "ザ"),
+    ("zi", "ジ"),
+    ("ji", "ジ"),
+    ("zu", "ズ"),
+    ("ze", "ゼ"),
+    ("zo", "ゾ"),
+    ("ja", "ジャ"),
+    ("ju", "ジュ"),
+    ("je", "ジェ"),
+    ("jo", "ジョ"),
+    ("jya", "ジャ"),
+    ("jyu", "ジュ"),
+    ("jyo", "ジョ"),
+    ("zya", "ジャ"),
+    ("zyu", "ジュ"),
+    ("zyo", "ジョ"),
+    ("ta", "タ"),
+    ("ti", "チ"),
+    ("chi", "チ"),
+    ("tu", "ツ"),
+    ("tsu", "ツ"),
+    ("te", "テ"),
+    ("to", "ト"),
+    ("cha", "チャ"),
+    ("chu", "チュ"),
+    ("che", "チェ"),
+    ("cho", "チョ"),
+    ("tya", "チャ"),
+    ("tyu", "チュ"),
+    ("tyo", "チョ"),
+    ("da", "ダ"),
+    ("di", "ヂ"),
+    ("du", "ヅ"),
+    ("de", "デ"),
+    ("do", "ド"),
+    ("na", "ナ"),
+    ("ni", "ニ"),
+    ("nu", "ヌ"),
+    ("ne", "ネ"),
+    ("no", "ノ"),
+    ("nya", "ニャ"),
+    ("nyu", "ニュ"),
+    ("nyo", "ニョ"),
+    ("ha", "ハ"),
+    ("hi", "ヒ"),
+    ("hu", "フ"),
+    ("fu", "フ"),
+    ("he", "ヘ"),
+    ("ho", "ホ"),
+    ("hya", "ヒャ"),
+    ("hyu", "ヒュ"),
+    ("hyo", "ヒョ"),
+    ("fa", "ファ"),
+    ("fi", "フィ"),
+    ("fe", "フェ"),
+    ("fo", "フォ"),
+    ("ba", "バ"),
+    ("bi", "ビ"),
+    ("bu", "ブ"),
+    ("be", "ベ"),
+    ("bo", "ボ"),
+    ("bya", "ビャ"),
+    ("byu", "ビュ"),
+    ("byo", "ビョ"),
+    ("pa", "パ"),
+    ("pi", "ピ"),
+    ("pu", "プ"),
+    ("pe", "ペ"),
+    ("po", "ポ"),
+    ("pya", "ピャ"),
+    ("pyu", "ピュ"),
+    ("pyo", "ピョ"),
+    ("ma", "マ"),
+    ("mi", "ミ"),
+    ("mu", "ム"),
+    ("me", "メ"),
+    ("mo", "モ"),
+    ("mya", "ミャ"),
+    ("myu", "ミュ"),
+    ("myo", "ミョ"),
+    ("ya", "ヤ"),
+    ("yu", "ユ"),
+    ("yo", "ヨ"),
+    ("ra", "ラ"),
+    ("ri", "リ"),
+    ("ru", "ル"),
+    ("re", "レ"),
+    ("ro", "ロ"),
+    ("rya", "リャ"),
+    ("ryu", "リュ"),
+    ("ryo", "リョ"),
+    ("wa", "ワ"),
+    ("wo", "ヲ"),
+];
+
+static SYLLABLE_MAP: Lazy<HashMap<&'static str, &'static str>> =
+    Lazy::new(|| SYLLABLES.iter().copied().collect());
+
+fn is_vowel(c: u8) -> bool {
+    matches!(c, b'a' | b'i' | b'u' | b'e' | b'o')
+}
+
+/// Read a word written in Hepburn or Kunrei romaji as katakana, e.g. `arigatou` -> `アリガトウ`
+///
+/// Returns `None` unless the whole word parses as romaji. `n` before a consonant, `nn`, `n'`
+/// and `m` before `b` or `p` are read as `ン`, and doubled consonants as `ッ`.
+pub fn to_katakana(word: &str) -> Option<String> {
+    let word = word.to_ascii_lowercase();
+    let bytes = word.as_bytes();
+    if bytes.is_empty() || !bytes.iter().all(|&c| c.is_ascii_lowercase() || c == b'\'') {
+        return None;
+    }
+    let mut kana = String::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        let next = bytes.get(i + 1).copied();
+        if c == b'n' && !next.is_some_and(|n| is_vowel(n) || n == b'y') {
+            kana.push('ン');
+            let after = bytes.get(i + 2).copied();
+            let double = next == Some(b'n') && !after.is_some_and(|n| is_vowel(n) || n == b'y');
+            i += if next == Some(b'\'') || double { 2 } else { 1 };
+            continue;
+        }
+        // `m` before `b` and `p` in Hepburn, as in `shimbun`
+        if c == b'm' && matches!(next, Some(b'b' | b'p')) {
+            kana.push('ン');
+            i += 1;
+            continue;
+        }
+        // `kk`, `tt`, and `tch` as in `matcha`
+        if !is_vowel(c) && (next == Some(c) || (c == b't' && next == Some(b'c'))) {
+            kana.push('ッ');
+            i += 1;
+            continue;
+        }
+        let (len, syllable) = (1..=3)
+            .rev()
+            .filter_map(|len| {
+                let syllable = word.get(i..i + len)?;
+                SYLLABLE_MAP.get(syllable).map(|kana| (len, kana))
+            })
+            .next()?;
+        kana += syllable;
+        i += len;
+    }
+    Some(kana)
+}
@@ -5,17 +5,26 @@ use zstd::decode_all;
 
 use crate::error::{Error, Result};
 
-/// Parse a .sbv2 file binary
+/// Contents of a .sbv2 file
+pub struct Sbv2File {
+    pub style_vectors: Vec<u8>,
+    pub vits2: Vec<u8>,
+    /// Style-Bert-VITS2 `config.json`, missing in files written by older converters
+    pub config: Option<Vec<u8>>,
+}
+
+/// Read a .sbv2 file binary
 ///
 /// # Examples
 ///
 /// ```rs
-/// parse_sbv2file("tsukuyomi", std::fs::read("tsukuyomi.sbv2")?)?;
+/// let sbv2file = read_sbv2file(std::fs::read("tsukuyomi.sbv2")?)?;
 /// ```
-pub fn parse_sbv2file<P: AsRef<[u8]>>(sbv2_bytes: P) -> Result<(Vec<u8>, Vec<u8>)> {
+pub fn read_sbv2file<P: AsRef<[u8]>>(sbv2_bytes: P) -> Result<Sbv2File> {
     let mut arc = Archive::new(Cursor::new(decode_all(Cursor::new(sbv2_bytes.as_ref()))?));
     let mut vits2 = None;
     let mut style_vectors = None;
+    let mut config = None;
     let mut et = arc.entries()?;
     while let Some(Ok(mut e)) = et.next() {
         let pth = String::from_utf8_lossy(&e.path_bytes()).to_string();
@@ -24,6 +33,7 @@ pub fn parse_sbv2file<P: AsRef<[u8]>>(sbv2_bytes: P) -> Result<(Vec<u8>, Vec<u8>
         match pth.as_str() {
             "model.onnx" => vits2 = Some(b),
             "style_vectors.json" => style_vectors = Some(b),
+            "config.json" => config = Some(b),
             _ => continue,
         }
     }
@@ -33,5 +43,21 @@ pub fn parse_sbv2file<P: AsRef<[u8]>>(sbv2_bytes: P) -> Result<(Vec<u8>, Vec<u8>
     if vits2.is_none() {
         return Err(Error::ModelNotFoundError("vits2".to_string()));
     }
-    Ok((style_vectors.unwrap(), vits2.unwrap()))
+    Ok(Sbv2File {
+        style_vectors: style_vectors.unwrap(),
+        vits2: vits2.unwrap(),
+        config,
+    })
+}
+
+/// Parse a .sbv2 file binary
+///
+/// # Examples
+///
+/// ```rs
+/// parse_sbv2file("tsukuyomi", std::fs::read("tsukuyomi.sbv2")?)?;
+/// ```
+pub fn parse_sbv2file<P: AsRef<[u8]>>(sbv2_bytes: P) -> Result<(Vec<u8>, Vec<u8>)> {
+    let sbv2file = read_sbv2file(sbv2_bytes)?;
+    Ok((sbv2file.style_vectors, sbv2file.vits2))
 }
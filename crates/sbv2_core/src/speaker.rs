@@ -0,0 +1,104 @@
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A speaker of a multi-speaker model, `id` is passed to the model as `sid`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Speaker {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+struct Config {
+    data: ConfigData,
+}
+
+#[derive(Deserialize)]
+struct ConfigData {
+    #[serde(default)]
+    spk2id: HashMap<String, i64>,
+    #[serde(default)]
+    style2id: HashMap<String, i64>,
+    sampling_rate: Option<u32>,
+    n_speakers: Option<i64>,
+}
+
+/// What a Style-Bert-VITS2 `config.json` tells about a model
+#[derive(Debug, Clone, Default)]
+pub struct ModelConfig {
+    /// `data.spk2id`, sorted by id
+    pub speakers: Vec<Speaker>,
+    /// `data.style2id` names, in style id order
+    pub style_names: Vec<String>,
+    /// `data.sampling_rate`
+    pub sample_rate: Option<u32>,
+    /// `data.n_speakers`
+    pub n_speakers: Option<i64>,
+}
+
+impl ModelConfig {
+    /// Parse a `config.json`, also stored as `aivm_hyper_parameters` in AIVMX files
+    pub fn parse<P: AsRef<[u8]>>(json: P) -> Result<Self> {
+        let config: Config = serde_json::from_slice(json.as_ref())?;
+        let mut styles: Vec<(String, i64)> = config.data.style2id.into_iter().collect();
+        styles.sort_by_key(|x| x.1);
+        Ok(ModelConfig {
+            speakers: sorted(
+                config
+                    .data
+                    .spk2id
+                    .into_iter()
+                    .map(|(name, id)| Speaker { id, name })
+                    .collect(),
+            ),
+            style_names: styles.into_iter().map(|x| x.0).collect(),
+            sample_rate: config.data.sampling_rate,
+            n_speakers: config.data.n_speakers,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    #[serde(default)]
+    speakers: Vec<ManifestSpeaker>,
+}
+
+#[derive(Deserialize)]
+struct ManifestSpeaker {
+    name: String,
+    local_id: i64,
+}
+
+fn sorted(mut speakers: Vec<Speaker>) -> Vec<Speaker> {
+    speakers.sort_by_key(|x| x.id);
+    speakers
+}
+
+/// Read `data.spk2id` of a Style-Bert-VITS2 `config.json`
+///
+/// # Examples
+///
+/// ```rs
+/// let speakers = from_config(br#"{"data": {"spk2id": {"tsukuyomi": 0}}}"#)?;
+/// assert_eq!(speakers[0].name, "tsukuyomi");
+/// ```
+pub fn from_config<P: AsRef<[u8]>>(json: P) -> Result<Vec<Speaker>> {
+    Ok(ModelConfig::parse(json)?.speakers)
+}
+
+/// Read the speakers of an AIVM manifest, stored as `aivm_manifest` in AIVMX files
+pub fn from_aivm_manifest<P: AsRef<[u8]>>(json: P) -> Result<Vec<Speaker>> {
+    let manifest: Manifest = serde_json::from_slice(json.as_ref())?;
+    Ok(sorted(
+        manifest
+            .speakers
+            .into_iter()
+            .map(|x| Speaker {
+                id: x.local_id,
+                name: x.name,
+            })
+            .collect(),
+    ))
+}
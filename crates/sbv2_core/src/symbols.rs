@@ -0,0 +1,257 @@
+use crate::error::{Error, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const PAUSE: char = '、';
+const VARIATION_SELECTOR: char = '\u{FE0F}';
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+const KEYCAP: char = '\u{20E3}';
+
+/// Emoji names based on the Unicode CLDR Japanese short names
+static EMOJI_NAMES: Lazy<HashMap<String, String>> =
+    Lazy::new(|| serde_json::from_str(include_str!("./emoji_ja.json")).unwrap());
+
+const SYMBOLS: &[(&str, SymbolClass, &str)] = &[
+    ("♪", SymbolClass::Decoration, "音符"),
+    ("♫", SymbolClass::Decoration, "音符"),
+    ("♬", SymbolClass::Decoration, "音符"),
+    ("★", SymbolClass::Decoration, "黒星"),
+    ("☆", SymbolClass::Decoration, "白星"),
+    ("♡", SymbolClass::Decoration, "ハート"),
+    ("♥", SymbolClass::Decoration, "ハート"),
+    ("○", SymbolClass::Decoration, "丸"),
+    ("●", SymbolClass::Decoration, "黒丸"),
+    ("◎", SymbolClass::Decoration, "二重丸"),
+    ("◇", SymbolClass::Decoration, "ひし形"),
+    ("◆", SymbolClass::Decoration, "黒ひし形"),
+    ("□", SymbolClass::Decoration, "四角"),
+    ("■", SymbolClass::Decoration, "黒四角"),
+    ("△", SymbolClass::Decoration, "三角"),
+    ("▲", SymbolClass::Decoration, "黒三角"),
+    ("▽", SymbolClass::Decoration, "逆三角"),
+    ("▼", SymbolClass::Decoration, "黒逆三角"),
+    ("※", SymbolClass::Decoration, "米印"),
+    ("〒", SymbolClass::Decoration, "郵便マーク"),
+    ("＝", SymbolClass::Math, "イコール"),
+    ("=", SymbolClass::Math, "イコール"),
+    ("≠", SymbolClass::Math, "ノットイコール"),
+    ("＋", SymbolClass::Math, "プラス"),
+    ("+", SymbolClass::Math, "プラス"),
+    ("±", SymbolClass::Math, "プラスマイナス"),
+    ("×", SymbolClass::Math, "かける"),
+    ("÷", SymbolClass::Math, "わる"),
+    ("＜", SymbolClass::Math, "小なり"),
+    ("<", SymbolClass::Math, "小なり"),
+    ("＞", SymbolClass::Math, "大なり"),
+    (">", SymbolClass::Math, "大なり"),
+    ("≦", SymbolClass::Math, "小なりイコール"),
+    ("≧", SymbolClass::Math, "大なりイコール"),
+    ("∞", SymbolClass::Math, "無限大"),
+    ("√", SymbolClass::Math, "ルート"),
+    ("＆", SymbolClass::Sign, "アンド"),
+    ("&", SymbolClass::Sign, "アンド"),
+    ("＃", SymbolClass::Sign, "シャープ"),
+    ("#", SymbolClass::Sign, "シャープ"),
+    ("＠", SymbolClass::Sign, "アット"),
+    ("@", SymbolClass::Sign, "アット"),
+    ("％", SymbolClass::Sign, "パーセント"),
+    ("%", SymbolClass::Sign, "パーセント"),
+    ("§", SymbolClass::Sign, "セクション"),
+    ("©", SymbolClass::Sign, "コピーライト"),
+    ("®", SymbolClass::Sign, "登録商標"),
+    ("™", SymbolClass::Sign, "トレードマーク"),
+    ("→", SymbolClass::Arrow, "右矢印"),
+    ("←", SymbolClass::Arrow, "左矢印"),
+    ("↑", SymbolClass::Arrow, "上矢印"),
+    ("↓", SymbolClass::Arrow, "下矢印"),
+    ("⇒", SymbolClass::Arrow, "ならば"),
+    ("⇔", SymbolClass::Arrow, "同値"),
+];
+
+/// Kind of symbol, each with its own `SymbolMode`
+///
+/// - `Emoji`: Pictographs, read by their CLDR Japanese short names
+/// - `Decoration`: `♪`, `★`, `♡`, `○` and so on
+/// - `Math`: `＝`, `＋`, `×`, `＜` and so on
+/// - `Sign`: `＆`, `＃`, `＠`, `％` and so on
+/// - `Arrow`: `→`, `⇒` and so on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolClass {
+    Emoji,
+    Decoration,
+    Math,
+    Sign,
+    Arrow,
+}
+
+impl FromStr for SymbolClass {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "emoji" => Ok(Self::Emoji),
+            "decoration" => Ok(Self::Decoration),
+            "math" => Ok(Self::Math),
+            "sign" => Ok(Self::Sign),
+            "arrow" => Ok(Self::Arrow),
+            _ => Err(Error::ValueError(format!("unknown symbol class: {s}"))),
+        }
+    }
+}
+
+/// How a symbol is handled
+///
+/// - `Read`: Replace with its reading
+/// - `Drop`: Remove silently
+/// - `Pause`: Replace with `、`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolMode {
+    Read,
+    Drop,
+    Pause,
+}
+
+impl FromStr for SymbolMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "read" => Ok(Self::Read),
+            "drop" => Ok(Self::Drop),
+            "pause" => Ok(Self::Pause),
+            _ => Err(Error::ValueError(format!("unknown symbol mode: {s}"))),
+        }
+    }
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0x2300..=0x23FF | 0x3030 | 0x303D
+    )
+}
+
+fn is_modifier(c: char) -> bool {
+    c == VARIATION_SELECTOR || c == KEYCAP || matches!(c as u32, 0x1F3FB..=0x1F3FF)
+}
+
+/// Replaces emoji and symbols with their readings, pauses or nothing
+///
+/// Runs of the same symbol are handled once, so `♪♪♪` is read as `音符`.
+/// Emoji are dropped by default, as they were before, and other classes are read.
+///
+/// # Examples
+///
+/// ```rs
+/// let mut reader = SymbolReader::new();
+/// reader.set_mode(SymbolClass::Emoji, SymbolMode::Read);
+/// reader.set_mode(SymbolClass::Decoration, SymbolMode::Pause);
+/// assert_eq!(reader.read("やった🎉楽しい♪"), "やったクラッカー楽しい、");
+/// ```
+#[derive(Debug, Clone)]
+pub struct SymbolReader {
+    modes: HashMap<SymbolClass, SymbolMode>,
+    readings: HashMap<String, (SymbolClass, String)>,
+    max_len: usize,
+}
+
+impl Default for SymbolReader {
+    fn default() -> Self {
+        let mut reader = Self {
+            modes: HashMap::from([
+                (SymbolClass::Emoji, SymbolMode::Drop),
+                (SymbolClass::Decoration, SymbolMode::Read),
+                (SymbolClass::Math, SymbolMode::Read),
+                (SymbolClass::Sign, SymbolMode::Read),
+                (SymbolClass::Arrow, SymbolMode::Read),
+            ]),
+            readings: HashMap::new(),
+            max_len: 1,
+        };
+        for (emoji, name) in EMOJI_NAMES.iter() {
+            reader.insert(emoji, SymbolClass::Emoji, name);
+        }
+        for (symbol, class, reading) in SYMBOLS {
+            reader.insert(symbol, *class, reading);
+        }
+        reader
+    }
+}
+
+impl SymbolReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mode(&self, class: SymbolClass) -> SymbolMode {
+        self.modes[&class]
+    }
+
+    pub fn set_mode(&mut self, class: SymbolClass, mode: SymbolMode) {
+        self.modes.insert(class, mode);
+    }
+
+    /// Set modes from a list such as `emoji=read,decoration=pause`
+    pub fn set_modes_from_str(&mut self, modes: &str) -> Result<()> {
+        for entry in modes.split(',').filter(|x| !x.trim().is_empty()) {
+            let (class, mode) = entry.split_once('=').ok_or_else(|| {
+                Error::ValueError(format!("expected `class=mode`, got `{entry}`"))
+            })?;
+            self.set_mode(class.trim().parse()?, mode.trim().parse()?);
+        }
+        Ok(())
+    }
+
+    /// Add or replace the reading of a symbol
+    pub fn insert(&mut self, symbol: &str, class: SymbolClass, reading: &str) {
+        let symbol: String = symbol.chars().filter(|c| !is_modifier(*c)).collect();
+        self.max_len = self.max_len.max(symbol.chars().count());
+        self.readings.insert(symbol, (class, reading.to_string()));
+    }
+
+    fn push(&self, text: &mut String, class: SymbolClass, reading: &str) {
+        match self.mode(class) {
+            SymbolMode::Read => text.push_str(reading),
+            SymbolMode::Drop => {}
+            SymbolMode::Pause => {
+                if !text.ends_with(PAUSE) {
+                    text.push(PAUSE);
+                }
+            }
+        }
+    }
+
+    pub fn read(&self, text: &str) -> String {
+        let chars: Vec<char> = text.chars().filter(|c| !is_modifier(*c)).collect();
+        let mut result = String::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let found = (1..=self.max_len.min(chars.len() - i)).rev().find_map(|n| {
+                let symbol: String = chars[i..i + n].iter().collect();
+                self.readings.get(&symbol).map(|reading| (n, reading))
+            });
+            if let Some((n, (class, reading))) = found {
+                self.push(&mut result, *class, reading);
+                i += n;
+                while i + n <= chars.len() && chars[i - n..i] == chars[i..i + n] {
+                    i += n;
+                }
+            } else if is_emoji(chars[i]) {
+                // Unknown emoji, including the rest of its ZWJ sequence, has no reading
+                self.push(&mut result, SymbolClass::Emoji, "");
+                i += 1;
+                while i + 1 < chars.len() && chars[i] == ZERO_WIDTH_JOINER {
+                    i += 2;
+                }
+            } else {
+                result.push(chars[i]);
+                i += 1;
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,365 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+const DIGITS: [&str; 10] = ["零", "一", "二", "三", "四", "五", "六", "七", "八", "九"];
+const DIGIT_READINGS: [&str; 10] = [
+    "ゼロ",
+    "イチ",
+    "ニー",
+    "サン",
+    "ヨン",
+    "ゴー",
+    "ロク",
+    "ナナ",
+    "ハチ",
+    "キュー",
+];
+const SMALL_UNITS: [&str; 4] = ["", "十", "百", "千"];
+const LARGE_UNITS: [&str; 5] = ["", "万", "億", "兆", "京"];
+
+// Longer units first so that `km/h` wins over `km` and `mg` over `m`
+const UNITS: &[(&str, &str)] = &[
+    ("km/h", "キロメートル毎時"),
+    ("m/s", "メートル毎秒"),
+    ("mAh", "ミリアンペアアワー"),
+    ("kHz", "キロヘルツ"),
+    ("MHz", "メガヘルツ"),
+    ("GHz", "ギガヘルツ"),
+    ("kcal", "キロカロリー"),
+    ("cal", "カロリー"),
+    ("km²", "平方キロメートル"),
+    ("m²", "平方メートル"),
+    ("m³", "立方メートル"),
+    ("°C", "度"),
+    ("℃", "度"),
+    ("°", "度"),
+    ("km", "キロメートル"),
+    ("cm", "センチメートル"),
+    ("mm", "ミリメートル"),
+    ("kg", "キログラム"),
+    ("mg", "ミリグラム"),
+    ("mL", "ミリリットル"),
+    ("ml", "ミリリットル"),
+    ("ms", "ミリ秒"),
+    ("kW", "キロワット"),
+    ("TB", "テラバイト"),
+    ("GB", "ギガバイト"),
+    ("MB", "メガバイト"),
+    ("KB", "キロバイト"),
+    ("kB", "キロバイト"),
+    ("Hz", "ヘルツ"),
+    ("dB", "デシベル"),
+    ("px", "ピクセル"),
+    ("m", "メートル"),
+    ("g", "グラム"),
+    ("L", "リットル"),
+    ("l", "リットル"),
+    ("t", "トン"),
+    ("W", "ワット"),
+    ("V", "ボルト"),
+];
+
+const NUMBER: &str = r"[0-9]{1,3}(?:,[0-9]{3})+(?:\.[0-9]+)?|[0-9]+(?:\.[0-9]+)?";
+
+static EMAIL_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[A-Za-z0-9._%+\-]+@[A-Za-z0-9\-]+(?:\.[A-Za-z0-9\-]+)+").unwrap());
+static URL_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"https?://([A-Za-z0-9\-.]+)(?::[0-9]+)?[^\s]*").unwrap());
+static PHONE_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"0[0-9]{1,4}-[0-9]{1,4}-[0-9]{3,4}|\+[0-9]{1,3}-[0-9]{1,4}-[0-9]{1,4}-[0-9]{3,4}")
+        .unwrap()
+});
+static DATE_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"([0-9]{4})[/\-.]([0-9]{1,2})[/\-.]([0-9]{1,2})").unwrap());
+static ERA_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(明治|大正|昭和|平成|令和)([0-9]{1,2})年").unwrap());
+static TIME_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"([0-9]{1,2}):([0-9]{2})(?::([0-9]{2}))?").unwrap());
+static VERSION_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(^|[^A-Za-z])[vV](?:er\.?\s?|ersion\s?)?([0-9]+(?:\.[0-9]+)*)|()([0-9]+\.[0-9]+\.[0-9]+(?:\.[0-9]+)*)",
+    )
+    .unwrap()
+});
+static CURRENCY_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(&format!(r"([¥￥$€£])\s?({NUMBER})")).unwrap());
+static PERCENT_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(&format!(r"({NUMBER})\s?%")).unwrap());
+static UNIT_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    let units: Vec<String> = UNITS.iter().map(|(unit, _)| regex::escape(unit)).collect();
+    Regex::new(&format!(
+        r"({NUMBER})\s?({})([^A-Za-z/]|$)",
+        units.join("|")
+    ))
+    .unwrap()
+});
+static NUMBER_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(NUMBER).unwrap());
+
+fn integer_to_kanji(digits: &str) -> String {
+    // Only ASCII digits are read, the patterns never match others
+    let mut digits: Vec<usize> = digits
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|d| d as usize)
+        .skip_while(|&d| d == 0)
+        .collect();
+    if digits.is_empty() {
+        return DIGITS[0].to_string();
+    }
+    digits.reverse();
+    if digits.len() > LARGE_UNITS.len() * 4 {
+        return digits.iter().rev().map(|&d| DIGITS[d]).collect();
+    }
+    let mut result = String::new();
+    for (i, group) in digits.chunks(4).enumerate().rev() {
+        let mut text = String::new();
+        for (j, &d) in group.iter().enumerate().rev() {
+            if d == 0 {
+                continue;
+            }
+            // 十, 百 and 千 are read without 一
+            if d != 1 || j == 0 {
+                text += DIGITS[d];
+            }
+            text += SMALL_UNITS[j];
+        }
+        if !text.is_empty() {
+            result += &text;
+            result += LARGE_UNITS[i];
+        }
+    }
+    result
+}
+
+/// Convert a number written in digits to kanji, e.g. `1,200.5` -> `千二百点五`
+pub fn number_to_kanji(number: &str) -> String {
+    let number = number.replace(',', "");
+    match number.split_once('.') {
+        Some((integer, fraction)) => {
+            let fraction: String = fraction
+                .chars()
+                .filter_map(|c| c.to_digit(10))
+                .map(|d| DIGITS[d as usize])
+                .collect();
+            format!("{}点{fraction}", integer_to_kanji(integer))
+        }
+        None => integer_to_kanji(&number),
+    }
+}
+
+/// Read digits one by one, e.g. `03` -> `ゼロサン`
+fn read_digits(digits: &str) -> String {
+    digits
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|d| DIGIT_READINGS[d as usize])
+        .collect()
+}
+
+fn read_domain(domain: &str) -> String {
+    domain.split('.').collect::<Vec<_>>().join("ドット")
+}
+
+fn to_halfwidth(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '０'..='９' | '％' | '：' | '／' | '．' | '＄' | '＠' => {
+                char::from_u32(c as u32 - 0xFEE0).unwrap()
+            }
+            c => c,
+        })
+        .collect()
+}
+
+/// Which kinds of expressions `TextNormalizer` rewrites
+///
+/// # Fields
+/// - `urls`: `https://example.com/a` -> host name only, `.` read as ドット
+/// - `emails`: `a@example.com` -> `aアットマークexampleドットcom`
+/// - `phone_numbers`: `03-1234-5678` -> digits read one by one, separated by の
+/// - `dates`: `2026/10/18` -> `二千二十六年十月十八日`, `令和8年` -> `令和八年`
+/// - `times`: `10:30` -> `十時三十分`
+/// - `versions`: `v1.2.3` -> `バージョン一点二点三`
+/// - `currency`: `¥1,200` -> `千二百円`, `$5` -> `五ドル`
+/// - `percent`: `50%` -> `五十パーセント`
+/// - `units`: `3.5kg` -> `三点五キログラム`, `25℃` -> `二十五度`
+/// - `numbers`: Other numbers with thousands separators or decimals to kanji
+#[derive(Debug, Clone)]
+pub struct NormalizerConfig {
+    pub urls: bool,
+    pub emails: bool,
+    pub phone_numbers: bool,
+    pub dates: bool,
+    pub times: bool,
+    pub versions: bool,
+    pub currency: bool,
+    pub percent: bool,
+    pub units: bool,
+    pub numbers: bool,
+}
+
+impl Default for NormalizerConfig {
+    fn default() -> Self {
+        Self {
+            urls: true,
+            emails: true,
+            phone_numbers: true,
+            dates: true,
+            times: true,
+            versions: true,
+            currency: true,
+            percent: true,
+            units: true,
+            numbers: true,
+        }
+    }
+}
+
+/// Rewrites numbers, dates, units and URLs to their Japanese reading
+///
+/// Runs before the English converter and `num2word`, so Latin letters left in
+/// URLs and e-mail addresses are converted to katakana afterwards.
+///
+/// # Examples
+///
+/// ```rs
+/// let normalizer = TextNormalizer::default();
+/// assert_eq!(normalizer.normalize("10:30に¥1,200"), "十時三十分に千二百円");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TextNormalizer {
+    pub config: NormalizerConfig,
+}
+
+impl TextNormalizer {
+    pub fn new(config: NormalizerConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn normalize(&self, text: &str) -> String {
+        let config = &self.config;
+        let mut text = to_halfwidth(text);
+        if config.emails {
+            text = EMAIL_PATTERN
+                .replace_all(&text, |caps: &Captures| {
+                    let (local, domain) = caps[0].split_once('@').unwrap();
+                    format!("{}アットマーク{}", read_domain(local), read_domain(domain))
+                })
+                .to_string();
+        }
+        if config.urls {
+            text = URL_PATTERN
+                .replace_all(&text, |caps: &Captures| read_domain(&caps[1]))
+                .to_string();
+        }
+        if config.phone_numbers {
+            text = PHONE_PATTERN
+                .replace_all(&text, |caps: &Captures| {
+                    let groups: Vec<String> = caps[0].split('-').map(read_digits).collect();
+                    groups.join("の")
+                })
+                .to_string();
+        }
+        if config.dates {
+            text = DATE_PATTERN
+                .replace_all(&text, |caps: &Captures| {
+                    let month: u32 = caps[2].parse().unwrap_or(0);
+                    let day: u32 = caps[3].parse().unwrap_or(0);
+                    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+                        return caps[0].to_string();
+                    }
+                    format!(
+                        "{}年{}月{}日",
+                        number_to_kanji(&caps[1]),
+                        number_to_kanji(&caps[2]),
+                        number_to_kanji(&caps[3])
+                    )
+                })
+                .to_string();
+            text = ERA_PATTERN
+                .replace_all(&text, |caps: &Captures| {
+                    format!("{}{}年", &caps[1], number_to_kanji(&caps[2]))
+                })
+                .to_string();
+        }
+        if config.times {
+            text = TIME_PATTERN
+                .replace_all(&text, |caps: &Captures| {
+                    let hour: u32 = caps[1].parse().unwrap_or(99);
+                    let minute: u32 = caps[2].parse().unwrap_or(99);
+                    let second: u32 = caps
+                        .get(3)
+                        .map_or("0", |s| s.as_str())
+                        .parse()
+                        .unwrap_or(99);
+                    if hour > 24 || minute > 59 || second > 59 {
+                        return caps[0].to_string();
+                    }
+                    let mut time = format!("{}時", number_to_kanji(&caps[1]));
+                    if minute != 0 {
+                        time += &format!("{}分", number_to_kanji(&caps[2]));
+                    }
+                    if second != 0 {
+                        time += &format!("{}秒", number_to_kanji(&caps[3]));
+                    }
+                    time
+                })
+                .to_string();
+        }
+        if config.versions {
+            text = VERSION_PATTERN
+                .replace_all(&text, |caps: &Captures| {
+                    let prefix = caps.get(1).or(caps.get(3)).map_or("", |m| m.as_str());
+                    let version = caps.get(2).or(caps.get(4)).unwrap().as_str();
+                    let parts: Vec<String> = version.split('.').map(number_to_kanji).collect();
+                    format!("{prefix}バージョン{}", parts.join("点"))
+                })
+                .to_string();
+        }
+        if config.currency {
+            text = CURRENCY_PATTERN
+                .replace_all(&text, |caps: &Captures| {
+                    let unit = match &caps[1] {
+                        "¥" | "￥" => "円",
+                        "$" => "ドル",
+                        "€" => "ユーロ",
+                        _ => "ポンド",
+                    };
+                    format!("{}{unit}", number_to_kanji(&caps[2]))
+                })
+                .to_string();
+        }
+        if config.percent {
+            text = PERCENT_PATTERN
+                .replace_all(&text, |caps: &Captures| {
+                    format!("{}パーセント", number_to_kanji(&caps[1]))
+                })
+                .to_string();
+        }
+        if config.units {
+            text = UNIT_PATTERN
+                .replace_all(&text, |caps: &Captures| {
+                    let reading = UNITS
+                        .iter()
+                        .find(|(unit, _)| *unit == &caps[2])
+                        .map(|(_, reading)| *reading)
+                        .unwrap();
+                    format!("{}{reading}{}", number_to_kanji(&caps[1]), &caps[3])
+                })
+                .to_string();
+        }
+        if config.numbers {
+            text = NUMBER_PATTERN
+                .replace_all(&text, |caps: &Captures| {
+                    let number = &caps[0];
+                    if number.contains([',', '.']) {
+                        number_to_kanji(number)
+                    } else {
+                        number.to_string()
+                    }
+                })
+                .to_string();
+        }
+        text
+    }
+}
@@ -1,4 +1,5 @@
-use crate::error::Result;
+use crate::error::{Error, Result};
+use ndarray::Array2;
 pub use tokenizers::Tokenizer;
 
 pub fn get_tokenizer<P: AsRef<[u8]>>(p: P) -> Result<Tokenizer> {
@@ -6,16 +7,103 @@ pub fn get_tokenizer<P: AsRef<[u8]>>(p: P) -> Result<Tokenizer> {
     Ok(tokenizer)
 }
 
-pub fn tokenize(text: &str, tokenizer: &Tokenizer) -> Result<(Vec<i64>, Vec<i64>)> {
-    let mut token_ids = vec![1];
-    let mut attention_masks = vec![1];
-    for content in text.chars() {
-        let token = tokenizer.encode(content.to_string(), false)?;
-        let ids = token.get_ids();
-        token_ids.extend(ids.iter().map(|&x| x as i64));
-        attention_masks.extend(token.get_attention_mask().iter().map(|&x| x as i64));
+fn special_id(tokenizer: &Tokenizer, token: &str, default: u32) -> i64 {
+    tokenizer.token_to_id(token).unwrap_or(default) as i64
+}
+
+/// Encode `text` so that token `i` is exactly character `i`
+///
+/// The whole text is encoded at once and the character offsets are checked.
+/// Only when the tokenizer merges or splits characters is each character encoded on its own.
+fn encode_chars(text: &str, tokenizer: &Tokenizer) -> Result<Vec<i64>> {
+    let encoding = tokenizer.encode_char_offsets(text, false)?;
+    let aligned = encoding.len() == text.chars().count()
+        && encoding
+            .get_offsets()
+            .iter()
+            .enumerate()
+            .all(|(i, &offset)| offset == (i, i + 1));
+    if aligned {
+        return Ok(encoding.get_ids().iter().map(|&x| x as i64).collect());
     }
-    token_ids.push(2);
-    attention_masks.push(1);
+    text.chars()
+        .enumerate()
+        .map(|(offset, c)| {
+            let encoding = tokenizer.encode(c.to_string(), false)?;
+            match encoding.get_ids() {
+                [id] => Ok(*id as i64),
+                ids => Err(Error::TokenAlignmentError {
+                    text: text.to_string(),
+                    offset,
+                    tokens: ids.len(),
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Tokenize `text` into one token per character, surrounded by `[CLS]` and `[SEP]`
+///
+/// `word2ph` relies on this alignment, so text the tokenizer cannot split into
+/// characters is reported as `Error::TokenAlignmentError`.
+pub fn tokenize(text: &str, tokenizer: &Tokenizer) -> Result<(Vec<i64>, Vec<i64>)> {
+    let mut token_ids = vec![special_id(tokenizer, "[CLS]", 1)];
+    token_ids.extend(encode_chars(text, tokenizer)?);
+    token_ids.push(special_id(tokenizer, "[SEP]", 2));
+    let attention_masks = vec![1; token_ids.len()];
     Ok((token_ids, attention_masks))
 }
+
+/// Padded tokens of several texts
+///
+/// # Fields
+/// - `token_ids`, `attention_masks`: `(batch, max_len)` arrays, padded with `[PAD]` and 0
+/// - `lengths`: Number of tokens of each text before padding
+#[derive(Debug, Clone)]
+pub struct TokenBatch {
+    pub token_ids: Array2<i64>,
+    pub attention_masks: Array2<i64>,
+    pub lengths: Vec<usize>,
+}
+
+impl TokenBatch {
+    /// Pad `(token_ids, attention_masks)` pairs returned by `tokenize`
+    pub fn new(tokens: &[(Vec<i64>, Vec<i64>)], pad_id: i64) -> Self {
+        let lengths: Vec<usize> = tokens.iter().map(|(ids, _)| ids.len()).collect();
+        let max_len = lengths.iter().copied().max().unwrap_or(0);
+        let mut token_ids = Array2::from_elem((tokens.len(), max_len), pad_id);
+        let mut attention_masks = Array2::zeros((tokens.len(), max_len));
+        for (i, (ids, masks)) in tokens.iter().enumerate() {
+            for (j, (&id, &mask)) in ids.iter().zip(masks).enumerate() {
+                token_ids[[i, j]] = id;
+                attention_masks[[i, j]] = mask;
+            }
+        }
+        TokenBatch {
+            token_ids,
+            attention_masks,
+            lengths,
+        }
+    }
+}
+
+/// Id used to pad `TokenBatch`
+pub fn pad_id(tokenizer: &Tokenizer) -> i64 {
+    special_id(tokenizer, "[PAD]", 0)
+}
+
+/// Tokenize several texts like `tokenize` and pad them to the same length
+///
+/// # Examples
+///
+/// ```rs
+/// let batch = tokenize_batch(&["コンニチワ", "サヨナラ"], &tokenizer)?;
+/// assert_eq!(batch.lengths, vec![7, 6]);
+/// ```
+pub fn tokenize_batch<S: AsRef<str>>(texts: &[S], tokenizer: &Tokenizer) -> Result<TokenBatch> {
+    let tokens = texts
+        .iter()
+        .map(|text| tokenize(text.as_ref(), tokenizer))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(TokenBatch::new(&tokens, pad_id(tokenizer)))
+}
@@ -1,13 +1,22 @@
+use crate::analysis::TextAnalysis;
+use crate::backend::InferenceBackend;
+use crate::bert_cache::{self, BertCache, BertCacheStats};
 use crate::error::{Error, Result};
+use crate::model::{ModelVariant, SessionConfig};
+use crate::preprocess::TextPreprocessor;
+use crate::speaker::{ModelConfig, Speaker};
+use crate::tts_util::PreparedText;
 use crate::{jtalk, model, style, tokenizer, tts_util};
 #[cfg(feature = "aivmx")]
 use base64::prelude::{Engine as _, BASE64_STANDARD};
 #[cfg(feature = "aivmx")]
 use ndarray::ShapeBuilder;
 use ndarray::{concatenate, Array1, Array2, Array3, Axis};
-use ort::session::Session;
+use std::fs::File;
 #[cfg(feature = "aivmx")]
 use std::io::Cursor;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
 use tokenizers::Tokenizer;
 
 #[derive(PartialEq, Eq, Clone)]
@@ -29,20 +38,44 @@ where
     }
 }
 
+/// VITS2 session and the input layout detected when it was loaded
+struct Vits2 {
+    session: Box<dyn InferenceBackend>,
+    variant: ModelVariant,
+}
+
 pub struct TTSModel {
-    vits2: Option<Session>,
+    vits2: Option<Vits2>,
     style_vectors: Array2<f32>,
+    config: ModelConfig,
     ident: TTSIdent,
     bytes: Option<Vec<u8>>,
+    session_config: SessionConfig,
+    warmup: bool,
+    pinned: bool,
+}
+
+impl TTSModel {
+    /// `data.sampling_rate` of the config, `tts_util::SAMPLE_RATE` without one
+    fn sample_rate(&self) -> u32 {
+        self.config.sample_rate.unwrap_or(tts_util::SAMPLE_RATE)
+    }
 }
 
+const WARMUP_KANA: &str = "コンニチワ'";
+
 /// High-level Style-Bert-VITS2's API
 pub struct TTSModelHolder {
     tokenizer: Tokenizer,
-    bert: Session,
+    bert: Box<dyn InferenceBackend>,
     models: Vec<TTSModel>,
     pub jtalk: jtalk::JTalk,
     max_loaded_models: Option<usize>,
+    bert_cache: BertCache,
+    /// Session options for models loaded from now on, see `model::SessionConfig`
+    pub session_config: SessionConfig,
+    /// Whether models loaded from now on are warmed up, see `warmup_model`
+    pub warmup: bool,
 }
 
 impl TTSModelHolder {
@@ -51,14 +84,17 @@ impl TTSModelHolder {
     /// # Examples
     ///
     /// ```rs
-    /// let mut tts_holder = TTSModelHolder::new(std::fs::read("deberta.onnx")?, std::fs::read("tokenizer.json")?, None)?;
+    /// let mut tts_holder = TTSModelHolder::new(std::fs::read("deberta.onnx")?, std::fs::read("tokenizer.json")?, None, SessionConfig::default())?;
     /// ```
     pub fn new<P: AsRef<[u8]>>(
         bert_model_bytes: P,
         tokenizer_bytes: P,
         max_loaded_models: Option<usize>,
+        session_config: SessionConfig,
     ) -> Result<Self> {
-        let bert = model::load_model(bert_model_bytes, true)?;
+        let fingerprint =
+            bert_cache::fingerprint(bert_model_bytes.as_ref(), tokenizer_bytes.as_ref());
+        let bert = model::load_model(bert_model_bytes, true, &session_config, "bert")?;
         let jtalk = jtalk::JTalk::new()?;
         let tokenizer = tokenizer::get_tokenizer(tokenizer_bytes)?;
         Ok(TTSModelHolder {
@@ -67,6 +103,9 @@ impl TTSModelHolder {
             jtalk,
             tokenizer,
             max_loaded_models,
+            bert_cache: BertCache::default().with_fingerprint(fingerprint),
+            session_config,
+            warmup: false,
         })
     }
 
@@ -89,9 +128,16 @@ impl TTSModelHolder {
                     load = false;
                 }
             }
-            let model = model::load_model(&aivmx_bytes, false)?;
-            let metadata = model.metadata()?;
-            if let Some(aivm_style_vectors) = metadata.custom("aivm_style_vectors")? {
+            let model = load_vits2(&aivmx_bytes, &self.session_config, &ident)?;
+            let mut config = match model.session.metadata("aivm_hyper_parameters")? {
+                Some(hyper_parameters) => ModelConfig::parse(hyper_parameters)?,
+                None => ModelConfig::default(),
+            };
+            // The manifest has the display names of the speakers
+            if let Some(manifest) = model.session.metadata("aivm_manifest")? {
+                config.speakers = crate::speaker::from_aivm_manifest(manifest)?;
+            }
+            if let Some(aivm_style_vectors) = model.session.metadata("aivm_style_vectors")? {
                 let aivm_style_vectors = BASE64_STANDARD.decode(aivm_style_vectors)?;
                 let style_vectors = Cursor::new(&aivm_style_vectors);
                 let reader = npyz::NpyFile::new(style_vectors)?;
@@ -106,7 +152,6 @@ impl TTSModelHolder {
                     let true_shape = shape.set_f(order == npyz::Order::Fortran);
                     ndarray::Array2::from_shape_vec(true_shape, data)?
                 };
-                drop(metadata);
                 self.models.push(TTSModel {
                     vits2: if load { Some(model) } else { None },
                     bytes: if self.max_loaded_models.is_some() {
@@ -114,9 +159,16 @@ impl TTSModelHolder {
                     } else {
                         None
                     },
-                    ident,
+                    ident: ident.clone(),
                     style_vectors,
-                })
+                    config,
+                    session_config: self.session_config.clone(),
+                    warmup: self.warmup,
+                    pinned: false,
+                });
+                if load && self.warmup {
+                    self.warmup_model(ident)?;
+                }
             }
         }
         Ok(())
@@ -134,8 +186,12 @@ impl TTSModelHolder {
         ident: I,
         sbv2_bytes: P,
     ) -> Result<()> {
-        let (style_vectors, vits2) = crate::sbv2file::parse_sbv2file(sbv2_bytes)?;
-        self.load(ident, style_vectors, vits2)?;
+        let ident = ident.into();
+        let sbv2file = crate::sbv2file::read_sbv2file(sbv2_bytes)?;
+        self.load(ident.clone(), sbv2file.style_vectors, sbv2file.vits2)?;
+        if let Some(config) = sbv2file.config {
+            self.load_config(ident, config)?;
+        }
         Ok(())
     }
 
@@ -162,18 +218,25 @@ impl TTSModelHolder {
             }
             self.models.push(TTSModel {
                 vits2: if load {
-                    Some(model::load_model(&vits2_bytes, false)?)
+                    Some(load_vits2(&vits2_bytes, &self.session_config, &ident)?)
                 } else {
                     None
                 },
                 style_vectors: style::load_style(style_vectors_bytes)?,
-                ident,
+                config: ModelConfig::default(),
+                ident: ident.clone(),
                 bytes: if self.max_loaded_models.is_some() {
                     Some(vits2_bytes.as_ref().to_vec())
                 } else {
                     None
                 },
-            })
+                session_config: self.session_config.clone(),
+                warmup: self.warmup,
+                pinned: false,
+            });
+            if load && self.warmup {
+                self.warmup_model(ident)?;
+            }
         }
         Ok(())
     }
@@ -194,6 +257,20 @@ impl TTSModelHolder {
         }
     }
 
+    /// Unload a model and return it, so that it can be put back with `restore`
+    /// if the model replacing it fails to load
+    pub fn take<I: Into<TTSIdent>>(&mut self, ident: I) -> Option<TTSModel> {
+        let ident = ident.into();
+        let i = self.models.iter().position(|m| m.ident == ident)?;
+        Some(self.models.remove(i))
+    }
+
+    /// Put back a model returned by `take`, in place of any model loaded under its ident since
+    pub fn restore(&mut self, model: TTSModel) {
+        self.unload(model.ident.clone());
+        self.models.push(model);
+    }
+
     /// Parse text and return the input for synthesize
     ///
     /// # Note
@@ -209,7 +286,7 @@ impl TTSModelHolder {
             &self.jtalk,
             &self.tokenizer,
             |token_ids, attention_masks| {
-                crate::bert::predict(&mut self.bert, token_ids, attention_masks)
+                crate::bert::predict(self.bert.as_mut(), token_ids, attention_masks)
             },
         )
     }
@@ -226,11 +303,19 @@ impl TTSModelHolder {
             &self.jtalk,
             &self.tokenizer,
             |token_ids, attention_masks| {
-                crate::bert::predict(&mut self.bert, token_ids, attention_masks)
+                crate::bert::predict(self.bert.as_mut(), token_ids, attention_masks)
             },
         )
     }
 
+    fn get_model<I: Into<TTSIdent>>(&self, ident: I) -> Result<&TTSModel> {
+        let ident = ident.into();
+        self.models
+            .iter()
+            .find(|m| m.ident == ident)
+            .ok_or(Error::ModelNotFoundError(ident.to_string()))
+    }
+
     fn find_model<I: Into<TTSIdent>>(&mut self, ident: I) -> Result<&mut TTSModel> {
         let ident = ident.into();
         self.models
@@ -240,39 +325,165 @@ impl TTSModelHolder {
     }
     fn find_and_load_model<I: Into<TTSIdent>>(&mut self, ident: I) -> Result<bool> {
         let ident = ident.into();
-        let (bytes, style_vectors) = {
-            let model = self
-                .models
-                .iter()
-                .find(|m| m.ident == ident)
-                .ok_or(Error::ModelNotFoundError(ident.to_string()))?;
-            if model.vits2.is_some() {
-                return Ok(true);
-            }
-            (model.bytes.clone().unwrap(), model.style_vectors.clone())
-        };
-        self.unload(ident.clone());
-        let s = model::load_model(&bytes, false)?;
+        let model = self.find_model(ident.clone())?;
+        if model.vits2.is_some() {
+            return Ok(true);
+        }
+        let bytes = model
+            .bytes
+            .clone()
+            .ok_or(Error::ModelNotFoundError(ident.to_string()))?;
+        let session_config = model.session_config.clone();
+        let s = load_vits2(&bytes, &session_config, &ident)?;
         if let Some(max) = self.max_loaded_models {
             if self.models.iter().filter(|x| x.vits2.is_some()).count() >= max {
-                self.unload(self.models.first().unwrap().ident.clone());
+                self.evict();
             }
         }
-        self.models.push(TTSModel {
-            bytes: Some(bytes.to_vec()),
-            vits2: Some(s),
-            style_vectors,
-            ident: ident.clone(),
-        });
-        let model = self
+        // Keep models in load order, so that the least recently loaded one is evicted first
+        let i = self
             .models
             .iter()
-            .find(|m| m.ident == ident)
+            .position(|m| m.ident == ident)
             .ok_or(Error::ModelNotFoundError(ident.to_string()))?;
-        if model.vits2.is_some() {
-            return Ok(true);
+        let mut model = self.models.remove(i);
+        model.vits2 = Some(s);
+        let warmup = model.warmup;
+        self.models.push(model);
+        if warmup {
+            self.warmup_model(ident)?;
+        }
+        Ok(true)
+    }
+
+    /// Drop the session of the least recently loaded model that is not pinned,
+    /// its bytes are kept so that it can be loaded again
+    fn evict(&mut self) {
+        if let Some(model) = self
+            .models
+            .iter_mut()
+            .find(|m| m.vits2.is_some() && !m.pinned)
+        {
+            model.vits2 = None;
+        }
+    }
+
+    /// Run a short dummy synthesis so that the first request does not pay for
+    /// ONNX Runtime's arena allocation and kernel selection
+    ///
+    /// Models that are not in memory, e.g. evicted by `max_loaded_models`, are skipped and
+    /// `false` is returned. They are warmed up when loaded again if `set_warmup` was called.
+    pub fn warmup_model<I: Into<TTSIdent>>(&mut self, ident: I) -> Result<bool> {
+        let ident = ident.into();
+        if self.find_model(ident.clone())?.vits2.is_none() {
+            return Ok(false);
         }
-        Err(Error::ModelNotFoundError(ident.to_string()))
+        let prepared = tts_util::prepare_kana(WARMUP_KANA, &self.tokenizer)?;
+        // Bypass the BERT cache, which would skip the session
+        let bert_content = crate::bert::predict(
+            self.bert.as_mut(),
+            prepared.token_ids.clone(),
+            prepared.attention_masks.clone(),
+        )?;
+        let (bert_ori, phones, tones, lang_ids) = prepared.into_input(bert_content)?;
+        let style_vector = self.get_style_vector(ident.clone(), 0, 1.0)?;
+        let vits2 = self
+            .find_model(ident.clone())?
+            .vits2
+            .as_mut()
+            .ok_or(Error::ModelNotFoundError(ident.to_string()))?;
+        model::synthesize(
+            vits2.session.as_mut(),
+            vits2.variant,
+            bert_ori,
+            phones,
+            Array1::from_vec(vec![0]),
+            tones,
+            lang_ids,
+            style_vector,
+            0.0,
+            1.0,
+            0.677,
+            0.8,
+        )?;
+        Ok(true)
+    }
+
+    /// Set whether a model is warmed up whenever it is loaded into memory again
+    pub fn set_warmup<I: Into<TTSIdent>>(&mut self, ident: I, warmup: bool) -> Result<()> {
+        self.find_model(ident)?.warmup = warmup;
+        Ok(())
+    }
+
+    /// Set whether a model is kept in memory when `max_loaded_models` is reached
+    ///
+    /// Models are loaded beyond `max_loaded_models` when every loaded model is pinned.
+    pub fn set_pinned<I: Into<TTSIdent>>(&mut self, ident: I, pinned: bool) -> Result<()> {
+        self.find_model(ident)?.pinned = pinned;
+        Ok(())
+    }
+
+    /// Read the speakers, style names and sample rate of a model from its Style-Bert-VITS2 `config.json`
+    ///
+    /// # Examples
+    ///
+    /// ```rs
+    /// tts_holder.load_config("tsukuyomi", std::fs::read("config.json")?)?;
+    /// ```
+    pub fn load_config<I: Into<TTSIdent>, P: AsRef<[u8]>>(
+        &mut self,
+        ident: I,
+        config_bytes: P,
+    ) -> Result<()> {
+        self.find_model(ident)?.config = ModelConfig::parse(config_bytes)?;
+        Ok(())
+    }
+
+    /// Return the speakers of a model sorted by id, empty when the model has no speaker list
+    pub fn speakers<I: Into<TTSIdent>>(&self, ident: I) -> Result<Vec<Speaker>> {
+        Ok(self.get_model(ident)?.config.speakers.clone())
+    }
+
+    /// Return what is known about a model without loading it
+    pub fn model_info<I: Into<TTSIdent>>(&self, ident: I) -> Result<ModelInfo> {
+        let model = self.get_model(ident)?;
+        Ok(ModelInfo {
+            loaded: model.vits2.is_some(),
+            pinned: model.pinned,
+            warmup: model.warmup,
+            style_count: model.style_vectors.nrows(),
+            style_names: model.config.style_names.clone(),
+            speakers: model.config.speakers.clone(),
+            sample_rate: model.sample_rate(),
+        })
+    }
+
+    /// Look up the speaker id of a speaker name
+    pub fn speaker_id<I: Into<TTSIdent>>(&self, ident: I, name: &str) -> Result<i64> {
+        self.speakers(ident)?
+            .into_iter()
+            .find(|s| s.name == name)
+            .map(|s| s.id)
+            .ok_or_else(|| Error::SpeakerError(format!("unknown speaker name `{name}`")))
+    }
+
+    /// Reject speaker ids the model does not have before they reach VITS2
+    ///
+    /// Without a speaker list, ids below `data.n_speakers` of the config are accepted,
+    /// and any id when the config does not tell the speaker count.
+    fn check_speaker<I: Into<TTSIdent>>(&self, ident: I, speaker_id: i64) -> Result<()> {
+        let config = &self.get_model(ident)?.config;
+        let known = if !config.speakers.is_empty() {
+            config.speakers.iter().any(|s| s.id == speaker_id)
+        } else {
+            speaker_id >= 0 && config.n_speakers.is_none_or(|n| speaker_id < n)
+        };
+        if !known {
+            return Err(Error::SpeakerError(format!(
+                "speaker id {speaker_id} is out of range"
+            )));
+        }
+        Ok(())
     }
 
     /// Get style vector by style id and weight
@@ -288,40 +499,281 @@ impl TTSModelHolder {
         style::get_style_vector(&self.find_model(ident)?.style_vectors, style_id, weight)
     }
 
-    /// Synthesize text to audio
+    /// Parse kana notation and return the input for synthesize
+    ///
+    /// # Note
+    /// This function is for low-level usage, use `easy_synthesize_kana` for high-level usage.
+    #[allow(clippy::type_complexity)]
+    pub fn parse_kana(
+        &mut self,
+        kana: &str,
+    ) -> Result<(Array2<f32>, Array1<i64>, Array1<i64>, Array1<i64>)> {
+        crate::tts_util::parse_kana_blocking(kana, &self.tokenizer, |token_ids, attention_masks| {
+            crate::bert::predict(self.bert.as_mut(), token_ids, attention_masks)
+        })
+    }
+
+    /// Validate phones and tones and return the input for synthesize
+    ///
+    /// # Note
+    /// This function is for low-level usage, use `easy_synthesize_phones` for high-level usage.
+    #[allow(clippy::type_complexity)]
+    pub fn parse_phones(
+        &mut self,
+        phones: Vec<String>,
+        tones: Vec<i32>,
+        text: Option<&str>,
+    ) -> Result<(Array2<f32>, Array1<i64>, Array1<i64>, Array1<i64>)> {
+        crate::tts_util::parse_phones_blocking(
+            phones,
+            tones,
+            text,
+            &self.jtalk,
+            &self.tokenizer,
+            |token_ids, attention_masks| {
+                crate::bert::predict(self.bert.as_mut(), token_ids, attention_masks)
+            },
+        )
+    }
+
+    /// Add a custom preprocessing step, which runs after the built-in steps
+    pub fn add_preprocessor(&mut self, preprocessor: Box<dyn TextPreprocessor>) {
+        self.jtalk.preprocessors.push(preprocessor);
+    }
+
+    /// Load a preprocessing rule file, see `preprocess::RuleSet`
+    pub fn load_preprocess_rules<P: AsRef<[u8]>>(&mut self, json: P) -> Result<()> {
+        self.jtalk.preprocessors.load_rules(json)
+    }
+
+    /// Run the text front end and return the result of every stage
+    pub fn analyze_text(&self, text: &str) -> Result<TextAnalysis> {
+        crate::analysis::analyze_text(text, &self.jtalk, &self.tokenizer)
+    }
+
+    /// Set how many BERT outputs are kept for reuse, 0 (the default) disables the cache
     ///
     /// # Examples
     ///
     /// ```rs
-    /// let audio = tts_holder.easy_synthesize("tsukuyomi", "こんにちは", 0, SynthesizeOptions::default())?;
+    /// tts_holder.set_bert_cache_capacity(4096);
     /// ```
-    pub fn easy_synthesize<I: Into<TTSIdent> + Copy>(
+    pub fn set_bert_cache_capacity(&mut self, capacity: usize) {
+        self.bert_cache.set_capacity(capacity);
+    }
+
+    /// Return hit and miss counts of the BERT cache
+    pub fn bert_cache_stats(&self) -> BertCacheStats {
+        self.bert_cache.stats()
+    }
+
+    /// Remove every cached BERT output
+    pub fn clear_bert_cache(&mut self) {
+        self.bert_cache.clear();
+    }
+
+    /// Write the BERT cache to a file so it survives restarts
+    pub fn save_bert_cache<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.bert_cache.save(BufWriter::new(File::create(path)?))
+    }
+
+    /// SHA-256 of the BERT model and the tokenizer, see `bert_cache::fingerprint`
+    pub fn bert_fingerprint(&self) -> [u8; 32] {
+        self.bert_cache.fingerprint()
+    }
+
+    /// Read a file written by `save_bert_cache` with the same BERT model and tokenizer
+    pub fn load_bert_cache<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        self.bert_cache.load(BufReader::new(File::open(path)?))
+    }
+
+    fn predict_bert(&mut self, prepared: &PreparedText) -> Result<Array2<f32>> {
+        predict_bert_cached(self.bert.as_mut(), &mut self.bert_cache, prepared)
+    }
+
+    fn split_text<'a>(text: &'a str, options: &SynthesizeOptions) -> Vec<&'a str> {
+        if options.split_sentences {
+            text.split('\n').filter(|t| !t.is_empty()).collect()
+        } else {
+            vec![text]
+        }
+    }
+
+    /// Run BERT once for every sentence that is neither cached nor zero-filled
+    ///
+    /// BERT exports without a batch axis run once per sentence instead.
+    fn predict_bert_batch(&mut self, sentences: &[PreparedText]) -> Result<Vec<Array2<f32>>> {
+        if !crate::bert::supports_batch(self.bert.as_ref())? {
+            return sentences
+                .iter()
+                .map(|prepared| {
+                    predict_bert_cached(self.bert.as_mut(), &mut self.bert_cache, prepared)
+                })
+                .collect();
+        }
+        let mut contents: Vec<Option<Array2<f32>>> = sentences
+            .iter()
+            .map(|prepared| {
+                if !prepared.needs_bert() {
+                    Some(prepared.zero_bert())
+                } else {
+                    self.bert_cache.get(&prepared.bert_text)
+                }
+            })
+            .collect();
+        let missing: Vec<usize> = (0..sentences.len())
+            .filter(|&i| contents[i].is_none())
+            .collect();
+        if !missing.is_empty() {
+            let tokens: Vec<(Vec<i64>, Vec<i64>)> = missing
+                .iter()
+                .map(|&i| {
+                    (
+                        sentences[i].token_ids.clone(),
+                        sentences[i].attention_masks.clone(),
+                    )
+                })
+                .collect();
+            let batch = tokenizer::TokenBatch::new(&tokens, tokenizer::pad_id(&self.tokenizer));
+            let features = crate::bert::predict_batch(self.bert.as_mut(), &batch)?;
+            for (i, features) in missing.into_iter().zip(features) {
+                self.bert_cache
+                    .insert(sentences[i].bert_text.clone(), features.clone());
+                contents[i] = Some(features);
+            }
+        }
+        Ok(contents.into_iter().flatten().collect())
+    }
+
+    fn synthesize_prepared<I: Into<TTSIdent> + Copy>(
         &mut self,
         ident: I,
-        text: &str,
-        style_id: i32,
+        sentences: Vec<PreparedText>,
+        style_vector: Array1<f32>,
         speaker_id: i64,
-        options: SynthesizeOptions,
-    ) -> Result<Vec<u8>> {
-        self.find_and_load_model(ident)?;
-        let style_vector = self.get_style_vector(ident, style_id, options.style_weight)?;
-        let audio_array = if options.split_sentences {
-            let texts: Vec<&str> = text.split('\n').collect();
-            let mut audios = vec![];
-            for (i, t) in texts.iter().enumerate() {
-                if t.is_empty() {
-                    continue;
+        options: &SynthesizeOptions,
+    ) -> Result<Array3<f32>> {
+        let assist_mean = match options.assist_text.as_deref() {
+            Some(assist_text) if !assist_text.is_empty() => Some(tts_util::assist_bert_mean(
+                assist_text,
+                &self.jtalk,
+                &self.tokenizer,
+                |token_ids, attention_masks| {
+                    crate::bert::predict(self.bert.as_mut(), token_ids, attention_masks)
+                },
+            )?),
+            _ => None,
+        };
+        let blend = |bert_content: Array2<f32>| match &assist_mean {
+            Some(assist_mean) => {
+                tts_util::blend_assist_bert(bert_content, assist_mean, options.assist_text_weight)
+            }
+            None => bert_content,
+        };
+        // VITS2 exports without `y_lengths` cannot trim the padded audio, so they synthesize sentence by sentence
+        let batch = options.batch
+            && sentences.len() > 1
+            && self
+                .find_model(ident)?
+                .vits2
+                .as_ref()
+                .is_some_and(|vits2| model::supports_batch(vits2.session.as_ref()));
+        let audios = if batch {
+            let bert_contents = self.predict_bert_batch(&sentences)?;
+            let inputs = sentences
+                .into_iter()
+                .zip(bert_contents)
+                .map(|(prepared, bert_content)| prepared.into_input(blend(bert_content)))
+                .collect::<Result<Vec<_>>>()?;
+            let vits2 = self
+                .find_model(ident)?
+                .vits2
+                .as_mut()
+                .ok_or(Error::ModelNotFoundError(ident.into().to_string()))?;
+            model::synthesize_batch(
+                vits2.session.as_mut(),
+                vits2.variant,
+                inputs,
+                speaker_id,
+                style_vector,
+                options.sdp_ratio,
+                options.length_scale,
+                0.677,
+                0.8,
+            )?
+        } else if options.pipeline && sentences.len() > 1 {
+            let ident: TTSIdent = ident.into();
+            let vits2 = self
+                .models
+                .iter_mut()
+                .find(|m| m.ident == ident)
+                .and_then(|m| m.vits2.as_mut())
+                .ok_or(Error::ModelNotFoundError(ident.to_string()))?;
+            let bert = self.bert.as_mut();
+            let bert_cache = &mut self.bert_cache;
+            // BERT runs one sentence ahead of VITS2 on its own thread
+            std::thread::scope(|scope| {
+                let (sender, receiver) = std::sync::mpsc::sync_channel(1);
+                let producer = scope.spawn(move || -> Result<()> {
+                    for prepared in sentences {
+                        let bert_content = predict_bert_cached(bert, bert_cache, &prepared)?;
+                        if sender.send((prepared, bert_content)).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(())
+                });
+                let mut audios = vec![];
+                let mut result = Ok(());
+                for (prepared, bert_content) in receiver.iter() {
+                    let audio = prepared.into_input(blend(bert_content)).and_then(
+                        |(bert_ori, phones, tones, lang_ids)| {
+                            model::synthesize(
+                                vits2.session.as_mut(),
+                                vits2.variant,
+                                bert_ori,
+                                phones,
+                                Array1::from_vec(vec![speaker_id]),
+                                tones,
+                                lang_ids,
+                                style_vector.clone(),
+                                options.sdp_ratio,
+                                options.length_scale,
+                                0.677,
+                                0.8,
+                            )
+                        },
+                    );
+                    match audio {
+                        Ok(audio) => audios.push(audio),
+                        Err(e) => {
+                            result = Err(e);
+                            break;
+                        }
+                    }
                 }
-                let (bert_ori, phones, tones, lang_ids) = self.parse_text(t)?;
+                // Dropping the receiver stops the BERT thread after an error
+                drop(receiver);
+                producer
+                    .join()
+                    .map_err(|_| Error::OtherError("BERT thread panicked".to_string()))??;
+                result.map(|_| audios)
+            })?
+        } else {
+            let mut audios = vec![];
+            for prepared in sentences {
+                let bert_content = blend(self.predict_bert(&prepared)?);
+                let (bert_ori, phones, tones, lang_ids) = prepared.into_input(bert_content)?;
 
                 let vits2 = self
                     .find_model(ident)?
                     .vits2
                     .as_mut()
                     .ok_or(Error::ModelNotFoundError(ident.into().to_string()))?;
-                let audio = model::synthesize(
-                    vits2,
-                    bert_ori.to_owned(),
+                audios.push(model::synthesize(
+                    vits2.session.as_mut(),
+                    vits2.variant,
+                    bert_ori,
                     phones,
                     Array1::from_vec(vec![speaker_id]),
                     tones,
@@ -331,39 +783,48 @@ impl TTSModelHolder {
                     options.length_scale,
                     0.677,
                     0.8,
-                )?;
-                audios.push(audio.clone());
-                if i != texts.len() - 1 {
-                    audios.push(Array3::zeros((1, 1, 22050)));
-                }
+                )?);
             }
-            concatenate(
-                Axis(2),
-                &audios.iter().map(|x| x.view()).collect::<Vec<_>>(),
-            )?
-        } else {
-            let (bert_ori, phones, tones, lang_ids) = self.parse_text(text)?;
-
-            let vits2 = self
-                .find_model(ident)?
-                .vits2
-                .as_mut()
-                .ok_or(Error::ModelNotFoundError(ident.into().to_string()))?;
-            model::synthesize(
-                vits2,
-                bert_ori.to_owned(),
-                phones,
-                Array1::from_vec(vec![speaker_id]),
-                tones,
-                lang_ids,
-                style_vector,
-                options.sdp_ratio,
-                options.length_scale,
-                0.677,
-                0.8,
-            )?
+            audios
         };
-        tts_util::array_to_vec(audio_array)
+        // Half a second between sentences
+        let sample_rate = self.get_model(ident)?.sample_rate();
+        let silence = Array3::zeros((1, 1, sample_rate as usize / 2));
+        let mut views = vec![];
+        for (i, audio) in audios.iter().enumerate() {
+            if i != 0 {
+                views.push(silence.view());
+            }
+            views.push(audio.view());
+        }
+        Ok(concatenate(Axis(2), &views)?)
+    }
+
+    /// Synthesize text to audio
+    ///
+    /// # Examples
+    ///
+    /// ```rs
+    /// let audio = tts_holder.easy_synthesize("tsukuyomi", "こんにちは", 0, SynthesizeOptions::default())?;
+    /// ```
+    pub fn easy_synthesize<I: Into<TTSIdent> + Copy>(
+        &mut self,
+        ident: I,
+        text: &str,
+        style_id: i32,
+        speaker_id: i64,
+        options: SynthesizeOptions,
+    ) -> Result<Vec<u8>> {
+        self.check_speaker(ident, speaker_id)?;
+        self.find_and_load_model(ident)?;
+        let style_vector = self.get_style_vector(ident, style_id, options.style_weight)?;
+        let sentences = Self::split_text(text, &options)
+            .into_iter()
+            .map(|t| tts_util::prepare_text(t, None, &self.jtalk, &self.tokenizer))
+            .collect::<Result<Vec<_>>>()?;
+        let audio_array =
+            self.synthesize_prepared(ident, sentences, style_vector, speaker_id, &options)?;
+        tts_util::array_to_wav(audio_array, self.get_model(ident)?.sample_rate())
     }
 
     pub fn easy_synthesize_neo<I: Into<TTSIdent> + Copy>(
@@ -375,69 +836,126 @@ impl TTSModelHolder {
         speaker_id: i64,
         options: SynthesizeOptions,
     ) -> Result<Vec<u8>> {
+        self.check_speaker(ident, speaker_id)?;
         self.find_and_load_model(ident)?;
         let style_vector = self.get_style_vector(ident, style_id, options.style_weight)?;
-        let audio_array = if options.split_sentences {
-            let texts: Vec<&str> = text.split('\n').collect();
-            let mut audios = vec![];
-            for (i, t) in texts.iter().enumerate() {
-                if t.is_empty() {
-                    continue;
-                }
-                let (bert_ori, phones, tones, lang_ids) =
-                    self.parse_text_neo(t.to_string(), given_tones.clone())?;
+        let sentences = Self::split_text(text, &options)
+            .into_iter()
+            .map(|t| tts_util::prepare_text(t, given_tones.clone(), &self.jtalk, &self.tokenizer))
+            .collect::<Result<Vec<_>>>()?;
+        let audio_array =
+            self.synthesize_prepared(ident, sentences, style_vector, speaker_id, &options)?;
+        tts_util::array_to_wav(audio_array, self.get_model(ident)?.sample_rate())
+    }
 
-                let vits2 = self
-                    .find_model(ident)?
-                    .vits2
-                    .as_mut()
-                    .ok_or(Error::ModelNotFoundError(ident.into().to_string()))?;
-                let audio = model::synthesize(
-                    vits2,
-                    bert_ori.to_owned(),
-                    phones,
-                    Array1::from_vec(vec![speaker_id]),
-                    tones,
-                    lang_ids,
-                    style_vector.clone(),
-                    options.sdp_ratio,
-                    options.length_scale,
-                    0.677,
-                    0.8,
-                )?;
-                audios.push(audio.clone());
-                if i != texts.len() - 1 {
-                    audios.push(Array3::zeros((1, 1, 22050)));
-                }
-            }
-            concatenate(
-                Axis(2),
-                &audios.iter().map(|x| x.view()).collect::<Vec<_>>(),
-            )?
-        } else {
-            let (bert_ori, phones, tones, lang_ids) = self.parse_text(text)?;
+    /// Synthesize kana notation to audio, skipping MeCab analysis
+    ///
+    /// # Examples
+    ///
+    /// ```rs
+    /// let audio = tts_holder.easy_synthesize_kana("tsukuyomi", "コンニチワ'/キョ'ウワ", 0, 0, SynthesizeOptions::default())?;
+    /// ```
+    pub fn easy_synthesize_kana<I: Into<TTSIdent> + Copy>(
+        &mut self,
+        ident: I,
+        kana: &str,
+        style_id: i32,
+        speaker_id: i64,
+        options: SynthesizeOptions,
+    ) -> Result<Vec<u8>> {
+        self.check_speaker(ident, speaker_id)?;
+        self.find_and_load_model(ident)?;
+        let style_vector = self.get_style_vector(ident, style_id, options.style_weight)?;
+        let sentences = Self::split_text(kana, &options)
+            .into_iter()
+            .map(|t| tts_util::prepare_kana(t, &self.tokenizer))
+            .collect::<Result<Vec<_>>>()?;
+        let audio_array =
+            self.synthesize_prepared(ident, sentences, style_vector, speaker_id, &options)?;
+        tts_util::array_to_wav(audio_array, self.get_model(ident)?.sample_rate())
+    }
 
-            let vits2 = self
-                .find_model(ident)?
-                .vits2
-                .as_mut()
-                .ok_or(Error::ModelNotFoundError(ident.into().to_string()))?;
-            model::synthesize(
-                vits2,
-                bert_ori.to_owned(),
-                phones,
-                Array1::from_vec(vec![speaker_id]),
-                tones,
-                lang_ids,
-                style_vector,
-                options.sdp_ratio,
-                options.length_scale,
-                0.677,
-                0.8,
-            )?
-        };
-        tts_util::array_to_vec(audio_array)
+    /// Synthesize a raw phone and tone sequence to audio, bypassing the text front end
+    ///
+    /// The BERT features are taken from `text` when given, and zero-filled otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```rs
+    /// let phones = ["k", "o", "N", "n", "i", "ch", "i", "w", "a"].map(String::from).to_vec();
+    /// let audio = tts_holder.easy_synthesize_phones("tsukuyomi", phones, vec![0, 0, 1, 1, 1, 1, 1, 1, 1], Some("こんにちは"), 0, 0, SynthesizeOptions::default())?;
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn easy_synthesize_phones<I: Into<TTSIdent> + Copy>(
+        &mut self,
+        ident: I,
+        phones: Vec<String>,
+        tones: Vec<i32>,
+        text: Option<&str>,
+        style_id: i32,
+        speaker_id: i64,
+        options: SynthesizeOptions,
+    ) -> Result<Vec<u8>> {
+        self.check_speaker(ident, speaker_id)?;
+        self.find_and_load_model(ident)?;
+        let style_vector = self.get_style_vector(ident, style_id, options.style_weight)?;
+        let prepared = tts_util::prepare_phones(phones, tones, text, &self.jtalk, &self.tokenizer)?;
+        let audio_array =
+            self.synthesize_prepared(ident, vec![prepared], style_vector, speaker_id, &options)?;
+        tts_util::array_to_wav(audio_array, self.get_model(ident)?.sample_rate())
+    }
+}
+
+/// Summary of a model, see `TTSModelHolder::model_info`
+///
+/// # Fields
+/// - `loaded`: Whether the VITS2 session is in memory
+/// - `pinned`: Whether the model is never evicted, see `TTSModelHolder::set_pinned`
+/// - `warmup`: Whether the model is warmed up when loaded, see `TTSModelHolder::set_warmup`
+/// - `style_count`: Number of style vectors
+/// - `style_names`: Style names in style id order, empty when the model has no config
+/// - `speakers`: Speakers sorted by id, empty when the model has no config
+/// - `sample_rate`: Sample rate the model was trained at and of the synthesized WAV files
+#[derive(Debug, Clone)]
+pub struct ModelInfo {
+    pub loaded: bool,
+    pub pinned: bool,
+    pub warmup: bool,
+    pub style_count: usize,
+    pub style_names: Vec<String>,
+    pub speakers: Vec<Speaker>,
+    pub sample_rate: u32,
+}
+
+/// Load a VITS2 model and detect the variant of its inputs
+fn load_vits2<P: AsRef<[u8]>>(
+    bytes: P,
+    session_config: &SessionConfig,
+    ident: &TTSIdent,
+) -> Result<Vits2> {
+    let session = model::load_model(bytes, false, session_config, &ident.to_string())?;
+    let variant = ModelVariant::detect(session.as_ref())?;
+    Ok(Vits2 { session, variant })
+}
+
+fn predict_bert_cached(
+    bert: &mut dyn InferenceBackend,
+    bert_cache: &mut BertCache,
+    prepared: &PreparedText,
+) -> Result<Array2<f32>> {
+    if !prepared.needs_bert() {
+        return Ok(prepared.zero_bert());
+    }
+    if let Some(features) = bert_cache.get(&prepared.bert_text) {
+        return Ok(features);
     }
+    let features = crate::bert::predict(
+        bert,
+        prepared.token_ids.clone(),
+        prepared.attention_masks.clone(),
+    )?;
+    bert_cache.insert(prepared.bert_text.clone(), features.clone());
+    Ok(features)
 }
 
 /// Synthesize options
@@ -447,11 +965,20 @@ impl TTSModelHolder {
 /// - `length_scale`: Length scale
 /// - `style_weight`: Style weight
 /// - `split_sentences`: Split sentences
+/// - `assist_text`: Text whose BERT feature is blended in to steer emotion
+/// - `assist_text_weight`: Weight of the assist text, 0.7 like Style-Bert-VITS2
+/// - `batch`: Run BERT and VITS2 once for all sentences, see `bert::predict_batch` and `model::synthesize_batch`.
+///   Only exports made with `--batch` support it, others fall back to sentence by sentence
+/// - `pipeline`: Run BERT for the next sentence on another thread while VITS2 renders the current one
 pub struct SynthesizeOptions {
     pub sdp_ratio: f32,
     pub length_scale: f32,
     pub style_weight: f32,
     pub split_sentences: bool,
+    pub assist_text: Option<String>,
+    pub assist_text_weight: f32,
+    pub batch: bool,
+    pub pipeline: bool,
 }
 
 impl Default for SynthesizeOptions {
@@ -461,6 +988,10 @@ impl Default for SynthesizeOptions {
             length_scale: 1.0,
             style_weight: 1.0,
             split_sentences: true,
+            assist_text: None,
+            assist_text_weight: 0.7,
+            batch: false,
+            pipeline: false,
         }
     }
 }
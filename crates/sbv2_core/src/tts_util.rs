@@ -1,96 +1,288 @@
 use std::io::Cursor;
 
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::jtalk::JTalkProcess;
 use crate::mora::MORA_KATA_TO_MORA_PHONEMES;
-use crate::norm::PUNCTUATIONS;
-use crate::{jtalk, nlp, norm, tokenizer, utils};
+use crate::norm::{JP_SYMBOLS, PUNCTUATIONS};
+use crate::{jtalk, kana, nlp, norm, tokenizer, utils};
 use hound::{SampleFormat, WavSpec, WavWriter};
 use ndarray::{concatenate, s, Array, Array1, Array2, Array3, Axis};
 use tokenizers::Tokenizer;
 
 pub fn preprocess_parse_text(text: &str, jtalk: &jtalk::JTalk) -> Result<(String, JTalkProcess)> {
-    let text = jtalk.num2word(text)?;
+    let text = jtalk.preprocessors.process(text)?;
+    let text = jtalk.num2word(&text)?;
     let normalized_text = norm::normalize_text(&text);
 
     let process = jtalk.process_text(&normalized_text)?;
     Ok((normalized_text, process))
 }
 
-/// Parse text and return the input for synthesize
+/// Hidden size of the BERT features fed to VITS2
+pub const BERT_HIDDEN_SIZE: usize = 1024;
+
+/// Front-end output of a sentence, ready for BERT inference
+///
+/// # Fields
+/// - `bert_text`: Text passed to the tokenizer
+/// - `phones`, `tones`, `lang_ids`: Interspersed input sequences for VITS2
+/// - `word2ph`: Number of phones owned by each token
+/// - `token_ids`, `attention_masks`: Tokenized `bert_text`, empty when BERT is skipped
+pub struct PreparedText {
+    pub bert_text: String,
+    pub phones: Vec<i64>,
+    pub tones: Vec<i64>,
+    pub lang_ids: Vec<i64>,
+    pub word2ph: Vec<i32>,
+    pub token_ids: Vec<i64>,
+    pub attention_masks: Vec<i64>,
+}
+
+impl PreparedText {
+    fn new(
+        phones: Vec<String>,
+        tones: Vec<i32>,
+        mut word2ph: Vec<i32>,
+        bert_text: Option<String>,
+        tokenizer: &Tokenizer,
+    ) -> Result<Self> {
+        let (phones, tones, lang_ids) = nlp::cleaned_text_to_sequence(phones, tones)?;
+
+        let phones = utils::intersperse(&phones, 0);
+        let tones = utils::intersperse(&tones, 0);
+        let lang_ids = utils::intersperse(&lang_ids, 0);
+        for item in &mut word2ph {
+            *item *= 2;
+        }
+        word2ph[0] += 1;
+
+        let (bert_text, token_ids, attention_masks) = match bert_text {
+            Some(bert_text) => {
+                let (token_ids, attention_masks) = tokenizer::tokenize(&bert_text, tokenizer)?;
+                (bert_text, token_ids, attention_masks)
+            }
+            None => (String::new(), vec![], vec![]),
+        };
+        Ok(PreparedText {
+            bert_text,
+            phones,
+            tones,
+            lang_ids,
+            word2ph,
+            token_ids,
+            attention_masks,
+        })
+    }
+
+    /// Whether BERT has to be run, or the features are zero-filled
+    pub fn needs_bert(&self) -> bool {
+        !self.token_ids.is_empty()
+    }
+
+    /// Zero-filled BERT output, used when there is no text to run BERT on
+    pub fn zero_bert(&self) -> Array2<f32> {
+        Array2::zeros((self.word2ph.len(), BERT_HIDDEN_SIZE))
+    }
+
+    /// Expand the BERT output to phone level and return the input for synthesize
+    #[allow(clippy::type_complexity)]
+    pub fn into_input(
+        self,
+        bert_content: Array2<f32>,
+    ) -> Result<(Array2<f32>, Array1<i64>, Array1<i64>, Array1<i64>)> {
+        if self.word2ph.len() != bert_content.nrows() {
+            return Err(Error::Word2PhMismatchError {
+                text: self.bert_text,
+                word2ph: self.word2ph.len(),
+                bert: bert_content.nrows(),
+            });
+        }
+
+        let mut phone_level_feature = vec![];
+        for (i, reps) in self.word2ph.iter().enumerate() {
+            let repeat_feature = {
+                let (reps_rows, reps_cols) = (*reps, 1);
+                let arr_len = bert_content.slice(s![i, ..]).len();
+
+                let mut results: Array2<f32> =
+                    Array::zeros((reps_rows as usize, arr_len * reps_cols));
+
+                for j in 0..reps_rows {
+                    for k in 0..reps_cols {
+                        let mut view = results.slice_mut(s![j, k * arr_len..(k + 1) * arr_len]);
+                        view.assign(&bert_content.slice(s![i, ..]));
+                    }
+                }
+                results
+            };
+            phone_level_feature.push(repeat_feature);
+        }
+        let phone_level_feature = concatenate(
+            Axis(0),
+            &phone_level_feature
+                .iter()
+                .map(|x| x.view())
+                .collect::<Vec<_>>(),
+        )?;
+        let bert_ori = phone_level_feature.t();
+        Ok((
+            bert_ori.to_owned(),
+            self.phones.into(),
+            self.tones.into(),
+            self.lang_ids.into(),
+        ))
+    }
+}
+
+/// Run the text front end and tokenize the result
 ///
 /// # Note
 /// This function is for low-level usage, use `easy_synthesize` for high-level usage.
-#[allow(clippy::type_complexity)]
-pub async fn parse_text(
+pub fn prepare_text(
     text: &str,
+    given_tones: Option<Vec<i32>>,
     jtalk: &jtalk::JTalk,
     tokenizer: &Tokenizer,
-    bert_predict: impl FnOnce(
-        Vec<i64>,
-        Vec<i64>,
-    ) -> std::pin::Pin<
-        Box<dyn std::future::Future<Output = Result<ndarray::Array2<f32>>>>,
-    >,
-) -> Result<(Array2<f32>, Array1<i64>, Array1<i64>, Array1<i64>)> {
-    let (normalized_text, process) = preprocess_parse_text(text, jtalk)?;
-    let (phones, tones, mut word2ph) = process.g2p()?;
-    let (phones, tones, lang_ids) = nlp::cleaned_text_to_sequence(phones, tones);
-    let phones = utils::intersperse(&phones, 0);
-    let tones = utils::intersperse(&tones, 0);
-    let lang_ids = utils::intersperse(&lang_ids, 0);
-    for item in &mut word2ph {
-        *item *= 2;
+) -> Result<PreparedText> {
+    let (_, process) = preprocess_parse_text(text, jtalk)?;
+    let (phones, mut tones, word2ph) = process.g2p()?;
+    if let Some(given_tones) = given_tones {
+        tones = given_tones;
     }
-    word2ph[0] += 1;
 
-    let text = {
+    let bert_text = {
         let (seq_text, _) = process.text_to_seq_kata()?;
         seq_text.join("")
     };
-    let (token_ids, attention_masks) = tokenizer::tokenize(&text, tokenizer)?;
+    PreparedText::new(phones, tones, word2ph, Some(bert_text), tokenizer)
+}
 
-    let bert_content = bert_predict(token_ids, attention_masks).await?;
+/// Convert kana notation such as `コンニチワ'/キョ'ウワ` and tokenize the result
+///
+/// MeCab analysis is skipped and the BERT text is derived from the kana.
+///
+/// # Note
+/// This function is for low-level usage, use `easy_synthesize_kana` for high-level usage.
+pub fn prepare_kana(text: &str, tokenizer: &Tokenizer) -> Result<PreparedText> {
+    let phrases = kana::parse_kana(text)?;
+    let (phones, tones, word2ph, bert_text) = kana::phrases_to_phones(&phrases);
+    PreparedText::new(phones, tones, word2ph, Some(bert_text), tokenizer)
+}
+
+/// Validate a raw phone and tone sequence and tokenize the companion text
+///
+/// Phones are `JP_SYMBOLS` names or punctuations, and tones are 0 or 1.
+/// The BERT features are taken from `text` when given, spread evenly over the phones,
+/// and zero-filled otherwise.
+///
+/// # Note
+/// This function is for low-level usage, use `easy_synthesize_phones` for high-level usage.
+pub fn prepare_phones(
+    mut phones: Vec<String>,
+    mut tones: Vec<i32>,
+    text: Option<&str>,
+    jtalk: &jtalk::JTalk,
+    tokenizer: &Tokenizer,
+) -> Result<PreparedText> {
+    if phones.is_empty() || phones.len() != tones.len() {
+        return Err(Error::PhoneLengthError {
+            phones: phones.len(),
+            tones: tones.len(),
+        });
+    }
+    for (i, (phone, &tone)) in phones.iter().zip(tones.iter()).enumerate() {
+        if phone != "_"
+            && !JP_SYMBOLS.contains(&phone.as_str())
+            && !PUNCTUATIONS.contains(&phone.as_str())
+        {
+            return Err(Error::UnknownPhoneError {
+                word: phone.clone(),
+                offset: i,
+                phone: phone.clone(),
+            });
+        }
+        if tone != 0 && tone != 1 {
+            return Err(Error::InvalidToneError { tone, offset: i });
+        }
+    }
+    if phones.first().map(|x| x.as_str()) != Some("_") {
+        phones.insert(0, "_".to_string());
+        tones.insert(0, 0);
+    }
+    if phones.len() == 1 || phones.last().map(|x| x.as_str()) != Some("_") {
+        phones.push("_".to_string());
+        tones.push(0);
+    }
 
-    assert!(
-        word2ph.len() == text.chars().count() + 2,
-        "{} {}",
-        word2ph.len(),
-        normalized_text.chars().count()
-    );
+    let bert_text = match text {
+        Some(text) => {
+            let (_, process) = preprocess_parse_text(text, jtalk)?;
+            let (seq_text, _) = process.text_to_seq_kata()?;
+            Some(seq_text.join("")).filter(|x| !x.is_empty())
+        }
+        None => None,
+    };
+    let word2ph = match &bert_text {
+        Some(bert_text) => {
+            let mut word2ph = vec![1];
+            word2ph.append(&mut JTalkProcess::distribute_phone(
+                phones.len() as i32 - 2,
+                bert_text.chars().count() as i32,
+            ));
+            word2ph.push(1);
+            word2ph
+        }
+        None => vec![phones.len() as i32],
+    };
+    PreparedText::new(phones, tones, word2ph, bert_text, tokenizer)
+}
 
-    let mut phone_level_feature = vec![];
-    for (i, reps) in word2ph.iter().enumerate() {
-        let repeat_feature = {
-            let (reps_rows, reps_cols) = (*reps, 1);
-            let arr_len = bert_content.slice(s![i, ..]).len();
+/// Mean BERT feature of an assist text, as in upstream Style-Bert-VITS2's `assist_text`
+///
+/// # Note
+/// This function is for low-level usage, use `SynthesizeOptions::assist_text` for high-level usage.
+pub fn assist_bert_mean(
+    text: &str,
+    jtalk: &jtalk::JTalk,
+    tokenizer: &Tokenizer,
+    bert_predict: impl FnOnce(Vec<i64>, Vec<i64>) -> Result<ndarray::Array2<f32>>,
+) -> Result<Array1<f32>> {
+    let prepared = prepare_text(text, None, jtalk, tokenizer)?;
+    let bert_content = bert_predict(prepared.token_ids, prepared.attention_masks)?;
+    bert_content
+        .mean_axis(Axis(0))
+        .ok_or(Error::ValueError("Empty assist text".to_string()))
+}
 
-            let mut results: Array2<f32> = Array::zeros((reps_rows as usize, arr_len * reps_cols));
+/// Blend the mean BERT feature of an assist text into the BERT output, before the per-phone expansion
+pub fn blend_assist_bert(
+    bert_content: Array2<f32>,
+    assist_mean: &Array1<f32>,
+    weight: f32,
+) -> Array2<f32> {
+    bert_content * (1.0 - weight) + &(assist_mean * weight)
+}
 
-            for j in 0..reps_rows {
-                for k in 0..reps_cols {
-                    let mut view = results.slice_mut(s![j, k * arr_len..(k + 1) * arr_len]);
-                    view.assign(&bert_content.slice(s![i, ..]));
-                }
-            }
-            results
-        };
-        phone_level_feature.push(repeat_feature);
-    }
-    let phone_level_feature = concatenate(
-        Axis(0),
-        &phone_level_feature
-            .iter()
-            .map(|x| x.view())
-            .collect::<Vec<_>>(),
-    )?;
-    let bert_ori = phone_level_feature.t();
-    Ok((
-        bert_ori.to_owned(),
-        phones.into(),
-        tones.into(),
-        lang_ids.into(),
-    ))
+/// Parse text and return the input for synthesize
+///
+/// # Note
+/// This function is for low-level usage, use `easy_synthesize` for high-level usage.
+#[allow(clippy::type_complexity)]
+pub async fn parse_text(
+    text: &str,
+    jtalk: &jtalk::JTalk,
+    tokenizer: &Tokenizer,
+    bert_predict: impl FnOnce(
+        Vec<i64>,
+        Vec<i64>,
+    ) -> std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<ndarray::Array2<f32>>>>,
+    >,
+) -> Result<(Array2<f32>, Array1<i64>, Array1<i64>, Array1<i64>)> {
+    let prepared = prepare_text(text, None, jtalk, tokenizer)?;
+    let bert_content =
+        bert_predict(prepared.token_ids.clone(), prepared.attention_masks.clone()).await?;
+    prepared.into_input(bert_content)
 }
 
 /// Parse text and return the input for synthesize
@@ -105,77 +297,60 @@ pub fn parse_text_blocking(
     tokenizer: &Tokenizer,
     bert_predict: impl FnOnce(Vec<i64>, Vec<i64>) -> Result<ndarray::Array2<f32>>,
 ) -> Result<(Array2<f32>, Array1<i64>, Array1<i64>, Array1<i64>)> {
-    let text = jtalk.num2word(text)?;
-    let normalized_text = norm::normalize_text(&text);
-
-    let process = jtalk.process_text(&normalized_text)?;
-    let (phones, mut tones, mut word2ph) = process.g2p()?;
-    if let Some(given_tones) = given_tones {
-        tones = given_tones;
-    }
-    let (phones, tones, lang_ids) = nlp::cleaned_text_to_sequence(phones, tones);
+    let prepared = prepare_text(text, given_tones, jtalk, tokenizer)?;
+    let bert_content = bert_predict(prepared.token_ids.clone(), prepared.attention_masks.clone())?;
+    prepared.into_input(bert_content)
+}
 
-    let phones = utils::intersperse(&phones, 0);
-    let tones = utils::intersperse(&tones, 0);
-    let lang_ids = utils::intersperse(&lang_ids, 0);
-    for item in &mut word2ph {
-        *item *= 2;
-    }
-    word2ph[0] += 1;
+/// Parse kana notation and return the input for synthesize
+///
+/// # Note
+/// This function is for low-level usage, use `easy_synthesize_kana` for high-level usage.
+#[allow(clippy::type_complexity)]
+pub fn parse_kana_blocking(
+    text: &str,
+    tokenizer: &Tokenizer,
+    bert_predict: impl FnOnce(Vec<i64>, Vec<i64>) -> Result<ndarray::Array2<f32>>,
+) -> Result<(Array2<f32>, Array1<i64>, Array1<i64>, Array1<i64>)> {
+    let prepared = prepare_kana(text, tokenizer)?;
+    let bert_content = bert_predict(prepared.token_ids.clone(), prepared.attention_masks.clone())?;
+    prepared.into_input(bert_content)
+}
 
-    let text = {
-        let (seq_text, _) = process.text_to_seq_kata()?;
-        seq_text.join("")
+/// Validate phones and tones and return the input for synthesize
+///
+/// # Note
+/// This function is for low-level usage, use `easy_synthesize_phones` for high-level usage.
+#[allow(clippy::type_complexity)]
+pub fn parse_phones_blocking(
+    phones: Vec<String>,
+    tones: Vec<i32>,
+    text: Option<&str>,
+    jtalk: &jtalk::JTalk,
+    tokenizer: &Tokenizer,
+    bert_predict: impl FnOnce(Vec<i64>, Vec<i64>) -> Result<ndarray::Array2<f32>>,
+) -> Result<(Array2<f32>, Array1<i64>, Array1<i64>, Array1<i64>)> {
+    let prepared = prepare_phones(phones, tones, text, jtalk, tokenizer)?;
+    let bert_content = if prepared.needs_bert() {
+        bert_predict(prepared.token_ids.clone(), prepared.attention_masks.clone())?
+    } else {
+        prepared.zero_bert()
     };
-    let (token_ids, attention_masks) = tokenizer::tokenize(&text, tokenizer)?;
-
-    let bert_content = bert_predict(token_ids, attention_masks)?;
-
-    assert!(
-        word2ph.len() == text.chars().count() + 2,
-        "{} {}",
-        word2ph.len(),
-        normalized_text.chars().count()
-    );
-
-    let mut phone_level_feature = vec![];
-    for (i, reps) in word2ph.iter().enumerate() {
-        let repeat_feature = {
-            let (reps_rows, reps_cols) = (*reps, 1);
-            let arr_len = bert_content.slice(s![i, ..]).len();
+    prepared.into_input(bert_content)
+}
 
-            let mut results: Array2<f32> = Array::zeros((reps_rows as usize, arr_len * reps_cols));
+/// Sample rate of the WAV files `array_to_vec` writes, and of models without a config
+pub const SAMPLE_RATE: u32 = 44100;
 
-            for j in 0..reps_rows {
-                for k in 0..reps_cols {
-                    let mut view = results.slice_mut(s![j, k * arr_len..(k + 1) * arr_len]);
-                    view.assign(&bert_content.slice(s![i, ..]));
-                }
-            }
-            results
-        };
-        phone_level_feature.push(repeat_feature);
-    }
-    let phone_level_feature = concatenate(
-        Axis(0),
-        &phone_level_feature
-            .iter()
-            .map(|x| x.view())
-            .collect::<Vec<_>>(),
-    )?;
-    let bert_ori = phone_level_feature.t();
-    Ok((
-        bert_ori.to_owned(),
-        phones.into(),
-        tones.into(),
-        lang_ids.into(),
-    ))
+pub fn array_to_vec(audio_array: Array3<f32>) -> Result<Vec<u8>> {
+    array_to_wav(audio_array, SAMPLE_RATE)
 }
 
-pub fn array_to_vec(audio_array: Array3<f32>) -> Result<Vec<u8>> {
+/// Write audio as a WAV file of `sample_rate`, the rate the model outputs
+pub fn array_to_wav(audio_array: Array3<f32>, sample_rate: u32) -> Result<Vec<u8>> {
     let spec = WavSpec {
         channels: 1,
-        sample_rate: 44100,
+        sample_rate,
         bits_per_sample: 32,
         sample_format: SampleFormat::Float,
     };
@@ -191,14 +366,21 @@ pub fn array_to_vec(audio_array: Array3<f32>) -> Result<Vec<u8>> {
     Ok(cursor.into_inner())
 }
 
-pub fn kata_tone2phone_tone(kata_tone: Vec<(String, i32)>) -> Vec<(String, i32)> {
+pub fn kata_tone2phone_tone(kata_tone: Vec<(String, i32)>) -> Result<Vec<(String, i32)>> {
     let mut results = vec![("_".to_string(), 0)];
-    for (mora, tone) in kata_tone {
+    for (i, (mora, tone)) in kata_tone.into_iter().enumerate() {
         if PUNCTUATIONS.contains(&mora.as_str()) {
             results.push((mora, 0));
             continue;
         } else {
-            let (consonant, vowel) = MORA_KATA_TO_MORA_PHONEMES.get(&mora).unwrap();
+            let (consonant, vowel) =
+                MORA_KATA_TO_MORA_PHONEMES
+                    .get(&mora)
+                    .ok_or_else(|| Error::UnknownMoraError {
+                        word: mora.clone(),
+                        offset: i,
+                        mora: mora.clone(),
+                    })?;
             if let Some(consonant) = consonant {
                 results.push((consonant.to_string(), tone));
                 results.push((vowel.to_string(), tone));
@@ -208,5 +390,5 @@ pub fn kata_tone2phone_tone(kata_tone: Vec<(String, i32)>) -> Vec<(String, i32)>
         }
     }
     results.push(("_".to_string(), 0));
-    results
+    Ok(results)
 }
@@ -0,0 +1,71 @@
+use ndarray::Array2;
+use sbv2_core::bert_cache::{fingerprint, BertCache};
+
+#[test]
+fn test_bert_cache_eviction() {
+    let mut cache = BertCache::new(2);
+    cache.insert("ア".to_string(), Array2::zeros((1, 4)));
+    cache.insert("イ".to_string(), Array2::ones((1, 4)));
+    assert!(cache.get("ア").is_some());
+    cache.insert("ウ".to_string(), Array2::zeros((2, 4)));
+    assert!(cache.get("イ").is_none());
+    assert!(cache.get("ア").is_some());
+    assert!(cache.get("ウ").is_some());
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.len), (3, 1, 2));
+}
+
+#[test]
+fn test_bert_cache_save_load() {
+    let mut cache = BertCache::new(8);
+    cache.insert(
+        "コンニチワ".to_string(),
+        Array2::from_shape_fn((3, 2), |(i, j)| (i * 2 + j) as f32),
+    );
+    let mut buf = vec![];
+    cache.save(&mut buf).unwrap();
+    let mut loaded = BertCache::new(8);
+    loaded.load(buf.as_slice()).unwrap();
+    assert_eq!(loaded.get("コンニチワ"), cache.get("コンニチワ"));
+}
+
+#[test]
+fn test_bert_cache_fingerprint() {
+    let mut cache = BertCache::new(8).with_fingerprint(fingerprint(b"bert", b"tokenizer"));
+    cache.insert("ア".to_string(), Array2::ones((1, 4)));
+    let mut buf = vec![];
+    cache.save(&mut buf).unwrap();
+    let mut other = BertCache::new(8).with_fingerprint(fingerprint(b"bert2", b"tokenizer"));
+    assert!(other.load(buf.as_slice()).is_err());
+    assert!(other.is_empty());
+    let mut same = BertCache::new(8).with_fingerprint(fingerprint(b"bert", b"tokenizer"));
+    same.load(buf.as_slice()).unwrap();
+    assert_eq!(same.get("ア"), Some(Array2::ones((1, 4))));
+}
+
+#[test]
+fn test_bert_cache_load_corrupt() {
+    let mut cache = BertCache::new(8);
+    cache.insert("ア".to_string(), Array2::ones((2, 4)));
+    cache.insert("イ".to_string(), Array2::zeros((1, 4)));
+    let mut buf = vec![];
+    cache.save(&mut buf).unwrap();
+    // Every truncation, and lengths and shapes far past the end of the file
+    let mut files: Vec<Vec<u8>> = (0..buf.len()).map(|len| buf[..len].to_vec()).collect();
+    let header = 8 + 32 + 8;
+    for (offset, value) in [
+        (header, u64::MAX),
+        (header + 8 + 3, u64::MAX),
+        (header + 8 + 3, 1 << 62),
+        (header + 8 + 3 + 8, u64::MAX),
+    ] {
+        let mut file = buf.clone();
+        file[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        files.push(file);
+    }
+    for file in files {
+        let mut loaded = BertCache::new(8);
+        assert!(loaded.load(file.as_slice()).is_err());
+        assert!(loaded.is_empty());
+    }
+}
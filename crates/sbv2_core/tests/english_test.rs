@@ -0,0 +1,51 @@
+use sbv2_core::english::EnglishConverter;
+
+#[test]
+fn test_english_to_katakana() {
+    let mut converter = EnglishConverter::new();
+    assert_eq!(converter.convert("GitHubのAPI"), "ギットハブのエーピーアイ");
+    assert_eq!(converter.convert("iPhoneとNASA"), "アイフォーンとナサ");
+    assert_eq!(converter.convert("ＯＫ"), "オーケー");
+
+    converter.insert("BERT", "バート");
+    assert_eq!(converter.convert("BERTの論文"), "バートの論文");
+    converter.insert("konnichiwa", "コンニチワ");
+    assert_eq!(converter.convert("konnichiwa"), "コンニチワ");
+}
+
+#[test]
+fn test_english_is_not_read_as_romaji() {
+    let converter = EnglishConverter::new();
+    for (word, kana) in [
+        ("note", "ノート"),
+        ("date", "デート"),
+        ("case", "ケース"),
+        ("site", "サイト"),
+        ("same", "セイム"),
+    ] {
+        assert_eq!(converter.word_to_kana(word), kana);
+    }
+    // Words missing from the dictionary are read as romaji, or spelled out
+    assert_eq!(converter.word_to_kana("tame"), "タメ");
+    assert_eq!(converter.word_to_kana("tmp"), "ティーエムピー");
+}
+
+#[test]
+fn test_romaji_to_katakana() {
+    let converter = EnglishConverter::new();
+    for (word, kana) in [
+        ("arigatou", "アリガトウ"),
+        ("Arigatou", "アリガトウ"),
+        ("konnichiwa", "コンニチワ"),
+        ("sayounara", "サヨウナラ"),
+        ("shinkansen", "シンカンセン"),
+        ("tyotto", "チョット"),
+        ("matcha", "マッチャ"),
+        ("kan'i", "カンイ"),
+        ("shimbun", "シンブン"),
+        ("onna", "オンナ"),
+        ("fujisan", "フジサン"),
+    ] {
+        assert_eq!(converter.word_to_kana(word), kana, "{word}");
+    }
+}
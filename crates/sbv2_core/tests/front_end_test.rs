@@ -0,0 +1,100 @@
+use sbv2_core::error::Error;
+use sbv2_core::jtalk::{FallbackPolicy, JTalk};
+use sbv2_core::norm::normalize_text;
+use sbv2_core::tts::SynthesizeOptions;
+use sbv2_core::tts_util::{blend_assist_bert, kata_tone2phone_tone, preprocess_parse_text};
+
+fn jtalk(fallback: FallbackPolicy) -> JTalk {
+    let mut jtalk = JTalk::new().expect("Failed to initialize JTalk");
+    jtalk.fallback = fallback;
+    jtalk
+}
+
+fn g2p(jtalk: &JTalk, text: &str) -> Result<Vec<String>, Error> {
+    let (_, process) = preprocess_parse_text(text, jtalk)?;
+    Ok(process.g2p()?.0)
+}
+
+/// Whether `word` is found at `offset` in the normalized `text`
+fn is_at(jtalk: &JTalk, text: &str, word: &str, offset: usize) -> bool {
+    let text = jtalk.preprocessors.process(text).unwrap();
+    let normalized = normalize_text(&jtalk.num2word(&text).unwrap());
+    let found: String = normalized
+        .chars()
+        .skip(offset)
+        .take(word.chars().count())
+        .collect();
+    found == word
+}
+
+#[test]
+fn test_front_end_fallback_policies() {
+    let fail = jtalk(FallbackPolicy::Fail);
+    let skip = jtalk(FallbackPolicy::Skip);
+    let kana = jtalk(FallbackPolicy::Kana);
+    for text in ["", "こんにちは"] {
+        for jtalk in [&fail, &skip, &kana] {
+            assert!(g2p(jtalk, text).is_ok(), "{text}");
+        }
+    }
+    for text in [
+        "ー",
+        "ーーー",
+        "？？",
+        "ヰ",
+        "ｗｗｗ",
+        "…",
+        "ㇰ",
+        "ヷ",
+        "〜〜",
+        "テスト、ー",
+    ] {
+        match g2p(&fail, text) {
+            // Nothing to fall back from, so every policy reads the text the same way
+            Ok(phones) => {
+                assert_eq!(g2p(&skip, text).ok().as_ref(), Some(&phones), "{text}");
+                assert_eq!(g2p(&kana, text).ok().as_ref(), Some(&phones), "{text}");
+            }
+            Err(
+                Error::EmptyReadingError { word, offset }
+                | Error::UnknownMoraError { word, offset, .. },
+            ) => {
+                assert!(
+                    is_at(&fail, text, &word, offset),
+                    "{text}: {word} at {offset}"
+                );
+                // The unreadable word is dropped
+                assert!(preprocess_parse_text(text, &skip).is_ok(), "{text}");
+            }
+            Err(
+                Error::PhonemeMismatchError { word, offset, .. }
+                | Error::UnknownPhoneError { word, offset, .. },
+            ) => {
+                assert!(
+                    is_at(&fail, text, &word, offset),
+                    "{text}: {word} at {offset}"
+                );
+            }
+            Err(e) => panic!("{text}: unexpected error: {e}"),
+        }
+    }
+}
+
+#[test]
+fn test_kata_tone2phone_tone_error() {
+    let result = kata_tone2phone_tone(vec![("ア".to_string(), 0), ("ㇰ".to_string(), 1)]);
+    assert!(matches!(
+        result,
+        Err(Error::UnknownMoraError { offset: 1, .. })
+    ));
+}
+
+#[test]
+fn test_default_assist_blend() {
+    // The target text keeps 30% of its own features by default
+    let bert = ndarray::Array2::from_elem((2, 3), 1.0_f32);
+    let assist_mean = ndarray::Array1::from_elem(3, 0.0_f32);
+    let weight = SynthesizeOptions::default().assist_text_weight;
+    let blended = blend_assist_bert(bert, &assist_mean, weight);
+    assert!(blended.iter().all(|&x| (x - 0.3).abs() < 1e-6));
+}
@@ -29,3 +29,20 @@ fn test_jpreprocess_with_various_texts() {
         }
     }
 }
+
+#[test]
+fn test_accent_phrases_interrogative() {
+    let jtalk = JTalk::new().expect("Failed to initialize JTalk");
+
+    for (text, is_interrogative) in [("元気ですか？", true), ("元気です。", false)] {
+        let (_, process) = preprocess_parse_text(text, &jtalk).expect("Failed to parse text");
+        let phrases = process
+            .accent_phrases()
+            .expect("Failed to get accent phrases");
+        assert_eq!(
+            phrases.last().map(|x| x.is_interrogative),
+            Some(is_interrogative),
+            "{text}"
+        );
+    }
+}
@@ -0,0 +1,24 @@
+use sbv2_core::kana::{parse_kana, phrases_to_phones, to_kana};
+
+#[test]
+fn test_parse_kana() {
+    let phrases = parse_kana("コンニチワ'/キョ'ウワ").expect("Failed to parse kana");
+    assert_eq!(phrases.len(), 2);
+    assert_eq!(phrases[0].accent, 5);
+    assert_eq!(phrases[0].tones(), vec![0, 1, 1, 1, 1]);
+    assert_eq!(phrases[1].accent, 1);
+    assert_eq!(phrases[1].tones(), vec![1, 0, 0]);
+    assert_eq!(to_kana(&phrases), "コンニチワ'/キョ'ウワ");
+
+    let (phones, tones, word2ph, text) = phrases_to_phones(&phrases);
+    assert_eq!(phones.len(), tones.len());
+    assert_eq!(word2ph.len(), text.chars().count() + 2);
+    assert_eq!(word2ph.iter().sum::<i32>() as usize, phones.len());
+}
+
+#[test]
+fn test_parse_kana_errors() {
+    for text in ["コンニチワ", "'ア", "ア'イ'", "ア'？イ", "ー'", ""] {
+        assert!(parse_kana(text).is_err(), "{text} should be rejected");
+    }
+}
@@ -0,0 +1,320 @@
+#![cfg(feature = "mock")]
+use sbv2_core::backend::{mock, Backend};
+use sbv2_core::model::{ModelVariant, SessionConfig};
+use sbv2_core::tts::{SynthesizeOptions, TTSModelHolder};
+use std::io::Cursor;
+
+fn holder() -> TTSModelHolder {
+    let config = SessionConfig {
+        backend: Backend::Mock,
+        ..Default::default()
+    };
+    let mut holder = TTSModelHolder::new(Vec::new(), mock::tokenizer_json(), None, config).unwrap();
+    holder
+        .load("mock", mock::style_vectors_json(2), Vec::new())
+        .unwrap();
+    holder
+}
+
+fn samples(wav: Vec<u8>) -> u32 {
+    hound::WavReader::new(Cursor::new(wav)).unwrap().duration()
+}
+
+#[test]
+fn test_mock_synthesize_length() {
+    let mut holder = holder();
+    let short = holder
+        .easy_synthesize_kana("mock", "コ'レ", 1, 0, SynthesizeOptions::default())
+        .unwrap();
+    let long = holder
+        .easy_synthesize_kana("mock", "コンニチワ'", 1, 0, SynthesizeOptions::default())
+        .unwrap();
+    let slow = holder
+        .easy_synthesize_kana(
+            "mock",
+            "コンニチワ'",
+            1,
+            0,
+            SynthesizeOptions {
+                length_scale: 2.0,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(samples(short) % mock::SAMPLES_PER_PHONE as u32, 0);
+    let long = samples(long);
+    assert!(long > mock::SAMPLES_PER_PHONE as u32 * 4);
+    assert_eq!(samples(slow), long * 2);
+}
+
+#[test]
+fn test_mock_batch_and_pipeline() {
+    let mut holder = holder();
+    let kana = "コンニチワ'\nサヨ'ナラ";
+    let sequential = holder
+        .easy_synthesize_kana("mock", kana, 0, 0, SynthesizeOptions::default())
+        .unwrap();
+    let batch = holder
+        .easy_synthesize_kana(
+            "mock",
+            kana,
+            0,
+            0,
+            SynthesizeOptions {
+                batch: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let pipeline = holder
+        .easy_synthesize_kana(
+            "mock",
+            kana,
+            0,
+            0,
+            SynthesizeOptions {
+                pipeline: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(batch, sequential);
+    assert_eq!(pipeline, sequential);
+}
+
+#[test]
+fn test_mock_batch_fallback() {
+    // Stock exports have no batch axis on BERT and no `y_lengths` on VITS2
+    let stock_bert = mock::model_json(&mock::MockModel {
+        metadata: Some(Default::default()),
+        ..Default::default()
+    });
+    let stock_vits2 = mock::model_json(&mock::MockModel {
+        outputs: Some(vec!["output".to_string()]),
+        ..Default::default()
+    });
+    let kana = "コンニチワ'\nサヨ'ナラ";
+    let expected = holder()
+        .easy_synthesize_kana("mock", kana, 0, 0, SynthesizeOptions::default())
+        .unwrap();
+    for (bert, vits2) in [
+        (stock_bert.clone(), Vec::new()),
+        (Vec::new(), stock_vits2.clone()),
+        (stock_bert, stock_vits2),
+    ] {
+        let config = SessionConfig {
+            backend: Backend::Mock,
+            ..Default::default()
+        };
+        let mut holder = TTSModelHolder::new(bert, mock::tokenizer_json(), None, config).unwrap();
+        holder
+            .load("mock", mock::style_vectors_json(2), vits2)
+            .unwrap();
+        let batch = holder
+            .easy_synthesize_kana(
+                "mock",
+                kana,
+                0,
+                0,
+                SynthesizeOptions {
+                    batch: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(batch, expected);
+    }
+}
+
+#[test]
+fn test_mock_multilingual() {
+    let bert = ndarray::ArrayD::from_elem(vec![1, 4, 3], 0.5_f32);
+    let inputs = ModelVariant::Multilingual.bert_inputs(bert.clone());
+    let names: Vec<&str> = inputs.iter().map(|x| x.0).collect();
+    assert_eq!(names, ["bert", "ja_bert", "en_bert"]);
+    for (name, input) in inputs {
+        let input = input.into_f32().unwrap();
+        assert_eq!(input.shape(), bert.shape());
+        if name == "ja_bert" {
+            assert_eq!(input, bert);
+        } else {
+            assert!(input.iter().all(|&x| x == 0.0));
+        }
+    }
+
+    let kana = "コンニチワ'\nサヨ'ナラ";
+    let expected = holder()
+        .easy_synthesize_kana("mock", kana, 0, 0, SynthesizeOptions::default())
+        .unwrap();
+    // The mock rejects missing inputs and non-zero `bert` and `en_bert`
+    let multilingual = mock::model_json(&mock::MockModel {
+        inputs: Some(mock::multilingual_inputs()),
+        ..Default::default()
+    });
+    let config = SessionConfig {
+        backend: Backend::Mock,
+        ..Default::default()
+    };
+    let mut holder = TTSModelHolder::new(Vec::new(), mock::tokenizer_json(), None, config).unwrap();
+    holder
+        .load("mock", mock::style_vectors_json(2), multilingual)
+        .unwrap();
+    for options in [
+        SynthesizeOptions::default(),
+        SynthesizeOptions {
+            batch: true,
+            ..Default::default()
+        },
+        SynthesizeOptions {
+            pipeline: true,
+            ..Default::default()
+        },
+    ] {
+        let audio = holder
+            .easy_synthesize_kana("mock", kana, 0, 0, options)
+            .unwrap();
+        assert_eq!(audio, expected);
+    }
+}
+
+#[test]
+fn test_mock_speakers() {
+    let mut holder = holder();
+    holder
+        .load_config(
+            "mock",
+            br#"{"data": {"spk2id": {"b": 1, "a": 0}, "style2id": {"Neutral": 0, "Happy": 1}}}"#,
+        )
+        .unwrap();
+    let info = holder.model_info("mock").unwrap();
+    assert!(info.loaded);
+    assert_eq!(info.style_count, 2);
+    assert_eq!(info.style_names, ["Neutral", "Happy"]);
+    let names: Vec<String> = holder
+        .speakers("mock")
+        .unwrap()
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+    assert_eq!(names, ["a", "b"]);
+    assert_eq!(holder.speaker_id("mock", "b").unwrap(), 1);
+    assert!(holder.speaker_id("mock", "c").is_err());
+    assert!(holder
+        .easy_synthesize_kana("mock", "コ'レ", 0, 1, SynthesizeOptions::default())
+        .is_ok());
+    assert!(matches!(
+        holder.easy_synthesize_kana("mock", "コ'レ", 0, 2, SynthesizeOptions::default()),
+        Err(sbv2_core::error::Error::SpeakerError(_))
+    ));
+}
+
+#[test]
+fn test_mock_speaker_count() {
+    // Without a speaker list, only the ids below `n_speakers` are known, if the config has it
+    let speaker = |holder: &mut TTSModelHolder, speaker_id| {
+        holder.easy_synthesize_kana("mock", "コ'レ", 0, speaker_id, SynthesizeOptions::default())
+    };
+    let mut holder = holder();
+    assert!(speaker(&mut holder, 0).is_ok());
+    assert!(speaker(&mut holder, 5).is_ok());
+    assert!(matches!(
+        speaker(&mut holder, -1),
+        Err(sbv2_core::error::Error::SpeakerError(_))
+    ));
+    holder
+        .load_config("mock", br#"{"data": {"n_speakers": 2}}"#)
+        .unwrap();
+    assert!(speaker(&mut holder, 1).is_ok());
+    assert!(matches!(
+        speaker(&mut holder, 2),
+        Err(sbv2_core::error::Error::SpeakerError(_))
+    ));
+}
+
+#[test]
+fn test_mock_sample_rate() {
+    let mut holder = holder();
+    let kana = "コンニチワ'\nサヨ'ナラ";
+    let default = holder
+        .easy_synthesize_kana("mock", kana, 0, 0, SynthesizeOptions::default())
+        .unwrap();
+    holder
+        .load_config("mock", br#"{"data": {"sampling_rate": 22050}}"#)
+        .unwrap();
+    assert_eq!(holder.model_info("mock").unwrap().sample_rate, 22050);
+    let wav = holder
+        .easy_synthesize_kana("mock", kana, 0, 0, SynthesizeOptions::default())
+        .unwrap();
+    let reader = hound::WavReader::new(Cursor::new(&wav)).unwrap();
+    assert_eq!(reader.spec().sample_rate, 22050);
+    // The pause between the sentences stays half a second
+    assert_eq!(reader.duration() + 22050 / 2, samples(default));
+}
+
+#[test]
+fn test_mock_eviction() {
+    let config = SessionConfig {
+        backend: Backend::Mock,
+        ..Default::default()
+    };
+    let mut holder =
+        TTSModelHolder::new(Vec::new(), mock::tokenizer_json(), Some(1), config).unwrap();
+    for ident in ["a", "b", "c"] {
+        holder
+            .load(ident, mock::style_vectors_json(1), Vec::new())
+            .unwrap();
+    }
+    let loaded = |holder: &TTSModelHolder, ident: &str| holder.model_info(ident).unwrap().loaded;
+    assert!(loaded(&holder, "a") && !loaded(&holder, "b"));
+    holder
+        .easy_synthesize_kana("b", "コ'レ", 0, 0, SynthesizeOptions::default())
+        .unwrap();
+    // The evicted model is kept and can be loaded again
+    assert!(!loaded(&holder, "a") && loaded(&holder, "b"));
+    assert_eq!(holder.models().len(), 3);
+    holder.set_pinned("b", true).unwrap();
+    holder
+        .easy_synthesize_kana("a", "コ'レ", 0, 0, SynthesizeOptions::default())
+        .unwrap();
+    assert!(loaded(&holder, "a") && loaded(&holder, "b"));
+}
+
+#[test]
+fn test_mock_phone_rejections() {
+    use sbv2_core::error::Error;
+    let mut holder = holder();
+    let mut synthesize = |phones: &[&str], tones: Vec<i32>| {
+        holder.easy_synthesize_phones(
+            "mock",
+            phones.iter().map(|x| x.to_string()).collect(),
+            tones,
+            None,
+            0,
+            0,
+            SynthesizeOptions::default(),
+        )
+    };
+    assert!(synthesize(&["k", "o"], vec![0, 1]).is_ok());
+    assert!(matches!(
+        synthesize(&[], vec![]),
+        Err(Error::PhoneLengthError {
+            phones: 0,
+            tones: 0
+        })
+    ));
+    assert!(matches!(
+        synthesize(&["k", "o"], vec![0]),
+        Err(Error::PhoneLengthError {
+            phones: 2,
+            tones: 1
+        })
+    ));
+    assert!(matches!(
+        synthesize(&["k", "xx"], vec![0, 1]),
+        Err(Error::UnknownPhoneError { offset: 1, .. })
+    ));
+    assert!(matches!(
+        synthesize(&["k", "o"], vec![0, 2]),
+        Err(Error::InvalidToneError { tone: 2, offset: 1 })
+    ));
+}
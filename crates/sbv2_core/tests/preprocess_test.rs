@@ -0,0 +1,33 @@
+use sbv2_core::preprocess::PreprocessPipeline;
+
+#[test]
+fn test_preprocess_pipeline() {
+    let mut pipeline = PreprocessPipeline::new();
+    assert_eq!(pipeline.names(), vec!["normalizer", "symbols", "english"]);
+    pipeline
+        .load_rules(
+            r#"{
+                "name": "brands",
+                "rules": [
+                    { "pattern": "SBV2", "replacement": "エスビーブイツー" },
+                    { "pattern": "[★☆]+", "replacement": "", "regex": true }
+                ]
+            }"#,
+        )
+        .unwrap();
+    assert_eq!(
+        pipeline.names(),
+        vec!["brands", "normalizer", "symbols", "english"]
+    );
+    assert_eq!(
+        pipeline.process("★SBV2で¥100のAPI★").unwrap(),
+        "エスビーブイツーで百円のエーピーアイ"
+    );
+
+    pipeline.remove("english").unwrap();
+    assert_eq!(pipeline.process("API").unwrap(), "API");
+    assert!(pipeline.remove("english").is_err());
+    assert!(pipeline
+        .load_rules(r#"{"rules": [{"pattern": "(", "replacement": "", "regex": true}]}"#)
+        .is_err());
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "quantize")]
+use prost::Message;
+use sbv2_core::quantize::{
+    onnx::{GraphProto, ModelProto, NodeProto, OperatorSetIdProto, TensorProto},
+    quantize_dynamic,
+};
+
+fn matmul_model(opset: i64) -> Vec<u8> {
+    let weight: Vec<f32> = vec![0.5, -1.0, 0.25, 2.0, 0.0, -0.75];
+    ModelProto {
+        ir_version: 8,
+        opset_import: vec![OperatorSetIdProto {
+            domain: String::new(),
+            version: opset,
+        }],
+        graph: Some(GraphProto {
+            name: "test".to_string(),
+            node: vec![NodeProto {
+                op_type: "MatMul".to_string(),
+                input: vec!["x".to_string(), "w".to_string()],
+                output: vec!["y".to_string()],
+                ..Default::default()
+            }],
+            initializer: vec![TensorProto {
+                name: "w".to_string(),
+                dims: vec![2, 3],
+                data_type: 1,
+                raw_data: weight.iter().flat_map(|x| x.to_le_bytes()).collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+    .encode_to_vec()
+}
+
+#[test]
+fn test_quantize_dynamic_matmul() {
+    let (model, stats) = quantize_dynamic(&matmul_model(13)).unwrap();
+    assert_eq!((stats.quantized, stats.skipped), (1, 0));
+    let model = ModelProto::decode(model.as_slice()).unwrap();
+    let graph = model.graph.unwrap();
+    let ops: Vec<&str> = graph.node.iter().map(|x| x.op_type.as_str()).collect();
+    assert_eq!(
+        ops,
+        [
+            "DynamicQuantizeLinear",
+            "MatMulInteger",
+            "Cast",
+            "Mul",
+            "Mul"
+        ]
+    );
+    assert_eq!(graph.node[4].output, ["y"]);
+    assert!(graph.initializer.iter().all(|x| x.name != "w"));
+    let weight = graph
+        .initializer
+        .iter()
+        .find(|x| x.name == "w_quantized")
+        .unwrap();
+    let weight: Vec<i8> = weight.raw_data.iter().map(|&x| x as i8).collect();
+    assert_eq!(weight, [32, -64, 16, 127, 0, -48]);
+}
+
+#[test]
+fn test_quantize_dynamic_old_opset() {
+    assert!(quantize_dynamic(&matmul_model(10)).is_err());
+}
@@ -0,0 +1,17 @@
+use sbv2_core::symbols::{SymbolClass, SymbolMode, SymbolReader};
+
+#[test]
+fn test_symbol_reader() {
+    let mut reader = SymbolReader::new();
+    assert_eq!(reader.read("1+1=2"), "1プラス1イコール2");
+    assert_eq!(reader.read("やった🎉"), "やった");
+
+    reader.set_mode(SymbolClass::Emoji, SymbolMode::Read);
+    reader.set_mode(SymbolClass::Decoration, SymbolMode::Pause);
+    assert_eq!(reader.read("👍🏻最高😂😂"), "サムズアップ最高うれし泣き");
+    assert_eq!(reader.read("楽しい♪♪★次"), "楽しい、次");
+
+    reader.set_modes_from_str("math=drop").unwrap();
+    assert_eq!(reader.mode(SymbolClass::Math), SymbolMode::Drop);
+    assert!(reader.set_modes_from_str("emoji=loud").is_err());
+}
@@ -0,0 +1,50 @@
+use sbv2_core::textnorm::{number_to_kanji, NormalizerConfig, TextNormalizer};
+
+#[test]
+fn test_number_to_kanji() {
+    assert_eq!(number_to_kanji("0"), "零");
+    assert_eq!(number_to_kanji("1,200"), "千二百");
+    assert_eq!(number_to_kanji("1234567"), "百二十三万四千五百六十七");
+    assert_eq!(number_to_kanji("3.5"), "三点五");
+}
+
+#[test]
+fn test_normalize_text() {
+    let normalizer = TextNormalizer::default();
+    for (text, expected) in [
+        ("2026/10/18", "二千二十六年十月十八日"),
+        ("令和8年", "令和八年"),
+        ("10:30", "十時三十分"),
+        ("¥1,200と$5", "千二百円と五ドル"),
+        ("3.5kg", "三点五キログラム"),
+        ("100km/h", "百キロメートル毎時"),
+        ("25℃", "二十五度"),
+        ("50%", "五十パーセント"),
+        (
+            "03-1234-5678",
+            "ゼロサンのイチニーサンヨンのゴーロクナナハチ",
+        ),
+        ("v1.2.3", "バージョン一点二点三"),
+        ("https://example.com/docs", "exampleドットcom"),
+        ("info@example.jp", "infoアットマークexampleドットjp"),
+    ] {
+        assert_eq!(normalizer.normalize(text), expected);
+    }
+
+    let normalizer = TextNormalizer::new(NormalizerConfig {
+        times: false,
+        ..Default::default()
+    });
+    assert_eq!(normalizer.normalize("10:30"), "10:30");
+}
+
+#[test]
+fn test_normalize_non_ascii_digits() {
+    let normalizer = TextNormalizer::default();
+    // Devanagari digits are not numbers to the normalizer and are left as they are
+    for text in ["v१.२", "१,२३४", "३.५kg", "१०:३०", "२०२६/१०/१८", "५०%"]
+    {
+        assert_eq!(normalizer.normalize(text), text);
+    }
+    assert_eq!(number_to_kanji("१२"), "零");
+}
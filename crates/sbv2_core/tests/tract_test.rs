@@ -0,0 +1,222 @@
+#![cfg(all(feature = "tract", feature = "quantize"))]
+use prost::Message;
+use sbv2_core::backend::Backend;
+use sbv2_core::model::SessionConfig;
+use sbv2_core::quantize::onnx::{
+    AttributeProto, Dimension, DimensionValue, GraphProto, ModelProto, NodeProto,
+    OperatorSetIdProto, TensorProto, TensorShapeProto, TensorTypeProto, TypeProto, TypeValue,
+    ValueInfoProto,
+};
+use sbv2_core::tts::{SynthesizeOptions, TTSModelHolder};
+use sbv2_core::tts_util::BERT_HIDDEN_SIZE;
+use std::io::Cursor;
+
+const FLOAT: i32 = 1;
+const INT64: i32 = 7;
+
+/// Input or output of `elem_type` whose dimensions are sizes or named dynamic axes
+fn value(name: &str, elem_type: i32, dims: &[&str]) -> ValueInfoProto {
+    let dim = dims
+        .iter()
+        .map(|x| Dimension {
+            value: Some(match x.parse() {
+                Ok(size) => DimensionValue::DimValue(size),
+                Err(_) => DimensionValue::DimParam(x.to_string()),
+            }),
+            ..Default::default()
+        })
+        .collect();
+    ValueInfoProto {
+        name: name.to_string(),
+        r#type: Some(TypeProto {
+            value: Some(TypeValue::TensorType(TensorTypeProto {
+                elem_type,
+                shape: Some(TensorShapeProto { dim }),
+            })),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+fn node(op_type: &str, input: &[&str], output: &str, attribute: Vec<AttributeProto>) -> NodeProto {
+    NodeProto {
+        op_type: op_type.to_string(),
+        input: input.iter().map(|x| x.to_string()).collect(),
+        output: vec![output.to_string()],
+        attribute,
+        ..Default::default()
+    }
+}
+
+fn int(name: &str, i: i64) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        r#type: 2,
+        i,
+        ..Default::default()
+    }
+}
+
+fn ints(name: &str, ints: Vec<i64>) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        r#type: 7,
+        ints,
+        ..Default::default()
+    }
+}
+
+fn model(graph: GraphProto) -> Vec<u8> {
+    ModelProto {
+        ir_version: 8,
+        opset_import: vec![OperatorSetIdProto {
+            domain: String::new(),
+            version: 13,
+        }],
+        graph: Some(graph),
+        ..Default::default()
+    }
+    .encode_to_vec()
+}
+
+/// `(tokens, BERT_HIDDEN_SIZE)` features scaled from the token ids
+fn bert_model() -> Vec<u8> {
+    model(GraphProto {
+        name: "bert".to_string(),
+        node: vec![
+            node("Cast", &["input_ids"], "ids", vec![int("to", FLOAT as i64)]),
+            node(
+                "Transpose",
+                &["ids"],
+                "column",
+                vec![ints("perm", vec![1, 0])],
+            ),
+            node("Mul", &["column", "hidden"], "output", vec![]),
+        ],
+        initializer: vec![TensorProto {
+            name: "hidden".to_string(),
+            dims: vec![1, BERT_HIDDEN_SIZE as i64],
+            data_type: FLOAT,
+            float_data: vec![0.001; BERT_HIDDEN_SIZE],
+            ..Default::default()
+        }],
+        input: vec![
+            value("input_ids", INT64, &["1", "tokens"]),
+            value("attention_mask", INT64, &["1", "tokens"]),
+        ],
+        output: vec![value("output", FLOAT, &["tokens", "1024"])],
+        ..Default::default()
+    })
+}
+
+/// VITS2 with the inputs of a stock export, rendering one sample per phone
+fn vits2_model() -> Vec<u8> {
+    model(GraphProto {
+        name: "vits2".to_string(),
+        node: vec![
+            node(
+                "ReduceMean",
+                &["bert"],
+                "features",
+                vec![ints("axes", vec![1]), int("keepdims", 1)],
+            ),
+            node(
+                "Cast",
+                &["tones"],
+                "tones_float",
+                vec![int("to", FLOAT as i64)],
+            ),
+            node("Unsqueeze", &["tones_float", "axis"], "tones_3d", vec![]),
+            node("Add", &["features", "tones_3d"], "sum", vec![]),
+            node("Mul", &["sum", "length_scale"], "output", vec![]),
+            node("Identity", &["x_tst_lengths"], "y_lengths", vec![]),
+        ],
+        initializer: vec![TensorProto {
+            name: "axis".to_string(),
+            dims: vec![1],
+            data_type: INT64,
+            int64_data: vec![1],
+            ..Default::default()
+        }],
+        input: vec![
+            value("x_tst", INT64, &["batch", "phones"]),
+            value("x_tst_lengths", INT64, &["batch"]),
+            value("sid", INT64, &["batch"]),
+            value("tones", INT64, &["batch", "phones"]),
+            value("language", INT64, &["batch", "phones"]),
+            value("bert", FLOAT, &["batch", "1024", "phones"]),
+            value("style_vec", FLOAT, &["batch", "256"]),
+            value("length_scale", FLOAT, &["1"]),
+            value("sdp_ratio", FLOAT, &["1"]),
+            value("noise_scale", FLOAT, &["1"]),
+            value("noise_scale_w", FLOAT, &["1"]),
+        ],
+        output: vec![
+            value("output", FLOAT, &["batch", "1", "phones"]),
+            value("y_lengths", INT64, &["batch"]),
+        ],
+        ..Default::default()
+    })
+}
+
+fn tokenizer_json() -> Vec<u8> {
+    br#"{
+  "version": "1.0",
+  "truncation": null,
+  "padding": null,
+  "added_tokens": [],
+  "normalizer": null,
+  "pre_tokenizer": null,
+  "post_processor": null,
+  "decoder": null,
+  "model": {
+    "type": "WordLevel",
+    "vocab": {"[PAD]": 0, "[CLS]": 1, "[SEP]": 2, "[UNK]": 3},
+    "unk_token": "[UNK]"
+  }
+}"#
+    .to_vec()
+}
+
+fn style_vectors_json() -> Vec<u8> {
+    serde_json::to_vec(&serde_json::json!({
+        "shape": [1, 256],
+        "data": [vec![0.5_f32; 256]],
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_tract_smoke() {
+    let config = SessionConfig {
+        backend: Backend::Tract,
+        ..Default::default()
+    };
+    let mut holder = TTSModelHolder::new(bert_model(), tokenizer_json(), None, config).unwrap();
+    holder
+        .load("tract", style_vectors_json(), vits2_model())
+        .unwrap();
+    let kana = "コンニチワ'\nサヨ'ナラ";
+    let sequential = holder
+        .easy_synthesize_kana("tract", kana, 0, 0, SynthesizeOptions::default())
+        .unwrap();
+    let samples = hound::WavReader::new(Cursor::new(&sequential))
+        .unwrap()
+        .duration();
+    assert!(samples > 0);
+    // `y_lengths` trims the padding of the shorter sentence
+    let batch = holder
+        .easy_synthesize_kana(
+            "tract",
+            kana,
+            0,
+            0,
+            SynthesizeOptions {
+                batch: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(batch, sequential);
+}
@@ -8,6 +8,7 @@ use axum::{
 };
 use sbv2_core::tts_util::kata_tone2phone_tone;
 use sbv2_core::{
+    model::SessionConfig,
     tts::{SynthesizeOptions, TTSModelHolder},
     tts_util::preprocess_parse_text,
 };
@@ -77,7 +78,7 @@ async fn synthesis(
         .iter()
         .map(|query| (query.kana.clone(), query.tone))
         .collect::<Vec<_>>();
-    let phone_tone = kata_tone2phone_tone(phone_tone);
+    let phone_tone = kata_tone2phone_tone(phone_tone)?;
     let tones = phone_tone.iter().map(|(_, tone)| *tone).collect::<Vec<_>>();
     let buffer = {
         let mut tts_model = state.tts_model.lock().await;
@@ -110,6 +111,13 @@ impl AppState {
             env::var("HOLDER_MAX_LOADED_MODElS")
                 .ok()
                 .and_then(|x| x.parse().ok()),
+            SessionConfig {
+                backend: match env::var("INFERENCE_BACKEND") {
+                    Ok(backend) => backend.parse()?,
+                    Err(_) => Default::default(),
+                },
+                ..Default::default()
+            },
         )?;
         let models = env::var("MODELS_PATH").unwrap_or("models".to_string());
         let mut f = fs::read_dir(&models).await?;
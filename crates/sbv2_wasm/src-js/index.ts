@@ -27,6 +27,7 @@ export class ModelHolder {
 		style_weight: number = 1.0,
 		sdp_ratio: number = 0.4,
 		speed: number = 1.0,
+		speaker_id: number = 0,
 	) {
 		const mod = this.models.get(name);
 		if (!mod) throw new Error(`No model named ${name}`);
@@ -67,7 +68,7 @@ export class ModelHolder {
 						await vits2.run({
 							x_tst: b,
 							x_tst_lengths: new Tensor("int64", [b_d.length]),
-							sid: new Tensor("int64", [0]),
+							sid: new Tensor("int64", [speaker_id]),
 							tones: c,
 							language: d,
 							bert: a,
@@ -91,6 +92,9 @@ export class ModelHolder {
 			style,
 		);
 	}
+	public analyze(text: string) {
+		return JSON.parse(wasm.analyze_text(text, this.tok));
+	}
 	public async load(name: string, b: Uint8Array) {
 		const [style, vits2_b] = wasm.load_sbv2file(b);
 		const vits2 = await InferenceSession.create(vits2_b as Uint8Array, {
@@ -41,6 +41,11 @@ pub fn load_sbv2file(buf: js_sys::Uint8Array) -> Result<js_sys::Array, JsError>
     ]))
 }
 
+#[wasm_bindgen]
+pub fn analyze_text(text: &str, tokenizer: &TokenizerWrap) -> Result<String, JsError> {
+    Ok(analysis::analyze_text(text, &JTALK, &tokenizer.tokenizer)?.to_json()?)
+}
+
 #[allow(clippy::too_many_arguments)]
 #[wasm_bindgen]
 pub async fn synthesize(
@@ -23,6 +23,9 @@ parser = ArgumentParser()
 parser.add_argument("--style_file", required=True)
 parser.add_argument("--config_file", required=True)
 parser.add_argument("--model_file", required=True)
+parser.add_argument(
+    "--batch", action="store_true", help="output y_lengths for SynthesizeOptions::batch"
+)
 args = parser.parse_args()
 style_file = args.style_file
 config_file = args.config_file
@@ -95,7 +98,7 @@ model = get_net_g(
 
 
 def forward(x, x_len, sid, tone, lang, bert, style, length_scale, sdp_ratio, noise_scale, noise_scale_w):
-    return model.infer(
+    o, attn, y_mask, rest = model.infer(
         x,
         x_len,
         sid,
@@ -108,6 +111,11 @@ def forward(x, x_len, sid, tone, lang, bert, style, length_scale, sdp_ratio, noi
         noise_scale=noise_scale,
         noise_scale_w=noise_scale_w,
     )
+    if args.batch:
+        # Number of valid samples of each audio, the rest is padding
+        y_lengths = y_mask.sum(dim=(1, 2)).long() * hyper_parameters.data.hop_length
+        return o, y_lengths
+    return o, attn, y_mask, rest
 
 
 model.forward = forward
@@ -151,7 +159,7 @@ torch.onnx.export(
         "noise_scale",
         "noise_scale_w"
     ],
-    output_names=["output"],
+    output_names=["output", "y_lengths"] if args.batch else ["output"],
 )
 os.system(f"onnxsim ../../models/model_{out_name}.onnx ../../models/model_{out_name}.onnx")
 onnxfile = open(f"../../models/model_{out_name}.onnx", "rb").read()
@@ -167,6 +175,7 @@ with taropen(f"../../models/tmp_{out_name}.sbv2tar", "w") as w:
     add_tar("version.txt", version)
     add_tar("model.onnx", onnxfile)
     add_tar("style_vectors.json", stylefile)
+    add_tar("config.json", open(config_file, "rb").read())
 open(f"../../models/{out_name}.sbv2", "wb").write(
     ZstdCompressor(threads=-1, level=22).compress(
         open(f"../../models/tmp_{out_name}.sbv2tar", "rb").read()